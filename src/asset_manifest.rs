@@ -0,0 +1,144 @@
+//! Where to find the game's images, sounds and fonts, by name.
+//!
+//! Asset collections ask the [`AssetManifest`] for e.g. the `"duck"` image or
+//! the `"step1"` sound instead of hard-coding paths. The manifest in the
+//! `assets` folder points straight at the source files. Release builds run
+//! `tools/pack_assets` first, which packs images into atlases, keeps one audio
+//! format per sound for the target and writes a manifest that points at the
+//! results, so the game finds them without knowing they moved.
+//!
+//! The manifest is loaded before any [`LoadResource`] collection (see
+//! `asset_tracking.rs`), so their [`FromWorld`] impls can rely on it.
+//!
+//! [`LoadResource`]: crate::asset_tracking::LoadResource
+
+use bevy::{
+    prelude::*,
+    render::texture::{ImageLoaderSettings, ImageSampler},
+    utils::HashMap,
+};
+use serde::Deserialize;
+
+use crate::json_asset::RegisterJsonAsset;
+
+/// Where the manifest lives in the `assets` folder.
+pub const ASSET_MANIFEST_PATH: &str = "assets.manifest.json";
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_json_asset::<AssetManifest>(&["manifest.json"]);
+}
+
+#[derive(Resource, Asset, TypePath, Deserialize, Debug, Clone, Default)]
+pub struct AssetManifest {
+    #[serde(default)]
+    pub images: HashMap<String, ManifestImage>,
+    /// Paths of sounds, relative to the `assets` folder.
+    #[serde(default)]
+    pub sounds: HashMap<String, String>,
+    /// Paths of fonts, relative to the `assets` folder.
+    #[serde(default)]
+    pub fonts: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ManifestImage {
+    /// Path of the image (or the atlas it was packed into), relative to the
+    /// `assets` folder.
+    pub path: String,
+    /// Where the image is within `path`, if it was packed into an atlas.
+    #[serde(default)]
+    pub rect: Option<URect>,
+    /// Where the image was in the `assets` folder before it was packed, for
+    /// assets that refer to it by path (like level decorations).
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub sampler: ManifestSampler,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestSampler {
+    /// Smooth, for most images.
+    #[default]
+    Linear,
+    /// Blocky, for pixel art.
+    Nearest,
+}
+
+impl ManifestImage {
+    pub fn load(&self, assets: &AssetServer) -> Handle<Image> {
+        match self.sampler {
+            ManifestSampler::Linear => assets.load(self.path.clone()),
+            ManifestSampler::Nearest => assets.load_with_settings(
+                self.path.clone(),
+                |settings: &mut ImageLoaderSettings| {
+                    settings.sampler = ImageSampler::nearest();
+                },
+            ),
+        }
+    }
+
+    /// Where the image starts within its file, e.g. to offset a sprite sheet.
+    pub fn offset(&self) -> UVec2 {
+        self.rect.map_or(UVec2::ZERO, |rect| rect.min)
+    }
+}
+
+/// Cut `rect` out of `image` for a UI node, which can't be given a rect like a
+/// [`Sprite`] can. `None` until `image` has loaded.
+pub fn ui_atlas(
+    image: &Handle<Image>,
+    rect: URect,
+    images: &Assets<Image>,
+    layouts: &mut Assets<TextureAtlasLayout>,
+) -> Option<TextureAtlas> {
+    let mut layout = TextureAtlasLayout::new_empty(images.get(image)?.size());
+    let index = layout.add_texture(rect);
+    Some(TextureAtlas {
+        layout: layouts.add(layout),
+        index,
+    })
+}
+
+impl AssetManifest {
+    /// The image called `name`. If the manifest doesn't have it, it's looked
+    /// for at `name` instead, and shows up as a failed load if it isn't there.
+    pub fn image(&self, name: &str) -> ManifestImage {
+        self.images.get(name).cloned().unwrap_or_else(|| {
+            warn!("The asset manifest has no image called {:?}", name);
+            ManifestImage {
+                path: name.to_string(),
+                rect: None,
+                source: None,
+                sampler: ManifestSampler::default(),
+            }
+        })
+    }
+
+    /// The image that was at `source` in the `assets` folder, if it moved.
+    pub fn image_from_source(&self, source: &str) -> Option<&ManifestImage> {
+        self.images
+            .values()
+            .find(|image| image.source.as_deref() == Some(source))
+    }
+
+    /// Load the sound called `name`. If the manifest doesn't have it, it's
+    /// looked for at `name` instead, like [`AssetManifest::image`].
+    pub fn load_sound(&self, assets: &AssetServer, name: &str) -> Handle<AudioSource> {
+        let path = self.sounds.get(name).cloned().unwrap_or_else(|| {
+            warn!("The asset manifest has no sound called {:?}", name);
+            name.to_string()
+        });
+        assets.load(path)
+    }
+
+    /// Load the font called `name`, like [`AssetManifest::load_sound`].
+    pub fn load_font(&self, assets: &AssetServer, name: &str) -> Handle<Font> {
+        let path = self.fonts.get(name).cloned().unwrap_or_else(|| {
+            warn!("The asset manifest has no font called {:?}", name);
+            name.to_string()
+        });
+        assets.load(path)
+    }
+}
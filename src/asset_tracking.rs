@@ -1,12 +1,42 @@
 //! A high-level way to load collections of asset handles as resources.
+//!
+//! The [`AssetManifest`] is loaded first, and the collections only once it's
+//! there, so they can look their assets up in it.
+//!
+//! How that's going is kept in [`LoadingProgress`], for the loading screen to
+//! show, along with any assets that failed to load. Send [`RetryFailedAssets`]
+//! to try those again.
 
-use std::collections::VecDeque;
+use bevy::{
+    asset::{LoadState, RecursiveDependencyLoadState, UntypedAssetId},
+    prelude::*,
+    utils::get_short_name,
+};
 
-use bevy::prelude::*;
+use crate::asset_manifest::{AssetManifest, ASSET_MANIFEST_PATH};
 
 pub(super) fn plugin(app: &mut App) {
-    app.init_resource::<ResourceHandles>();
-    app.add_systems(PreUpdate, load_resource_assets);
+    app.insert_resource(ResourceHandles(vec![TrackedResource {
+        name: get_short_name(std::any::type_name::<AssetManifest>()),
+        handle: None,
+        needs_manifest: false,
+        state: ResourceLoadState::Pending,
+        add: |world| {
+            world
+                .resource::<AssetServer>()
+                .load::<AssetManifest>(ASSET_MANIFEST_PATH)
+                .untyped()
+        },
+        insert: insert_loaded_resource::<AssetManifest>,
+        // If it fails, it's the manifest itself that did.
+        dependencies: |_, handle| vec![handle.id()],
+    }]));
+    app.init_resource::<LoadingProgress>();
+    app.add_event::<RetryFailedAssets>();
+    app.add_systems(
+        PreUpdate,
+        (retry_failed_assets, load_resource_assets).chain(),
+    );
 }
 
 pub trait LoadResource {
@@ -19,46 +49,250 @@ pub trait LoadResource {
 impl LoadResource for App {
     fn load_resource<T: Resource + Asset + Clone + FromWorld>(&mut self) -> &mut Self {
         self.init_asset::<T>();
-        let world = self.world_mut();
-        let value = T::from_world(world);
-        let assets = world.resource::<AssetServer>();
-        let handle = assets.add(value);
-        let mut handles = world.resource_mut::<ResourceHandles>();
-        handles
-            .waiting
-            .push_back((handle.untyped(), |world, handle| {
-                let assets = world.resource::<Assets<T>>();
-                if let Some(value) = assets.get(handle.id().typed::<T>()) {
-                    world.insert_resource(value.clone());
-                }
-            }));
+        self.world_mut()
+            .resource_mut::<ResourceHandles>()
+            .0
+            .push(TrackedResource {
+                name: get_short_name(std::any::type_name::<T>()),
+                // Added once the manifest is loaded.
+                handle: None,
+                needs_manifest: true,
+                state: ResourceLoadState::Pending,
+                add: add_resource_asset::<T>,
+                insert: insert_loaded_resource::<T>,
+                dependencies: resource_dependencies::<T>,
+            });
         self
     }
 }
 
+/// Add a fresh `T` to the asset server, which starts loading its dependencies
+/// (again, if they failed last time).
+fn add_resource_asset<T: Asset + FromWorld>(world: &mut World) -> UntypedHandle {
+    let value = T::from_world(world);
+    world.resource::<AssetServer>().add(value).untyped()
+}
+
+fn insert_loaded_resource<T: Resource + Asset + Clone>(world: &mut World, handle: &UntypedHandle) {
+    let assets = world.resource::<Assets<T>>();
+    if let Some(value) = assets.get(handle.id().typed::<T>()) {
+        world.insert_resource(value.clone());
+    }
+}
+
+fn resource_dependencies<T: Asset>(world: &World, handle: &UntypedHandle) -> Vec<UntypedAssetId> {
+    let mut dependencies = Vec::new();
+    if let Some(value) = world.resource::<Assets<T>>().get(handle.id().typed::<T>()) {
+        value.visit_dependencies(&mut |id| dependencies.push(id));
+    }
+    dependencies
+}
+
+/// A function that adds a resource's asset, returning its handle.
+type AddResourceAsset = fn(&mut World) -> UntypedHandle;
 /// A function that inserts a loaded resource.
 type InsertLoadedResource = fn(&mut World, &UntypedHandle);
+/// A function that lists the assets a resource's asset depends on.
+type ResourceDependencies = fn(&World, &UntypedHandle) -> Vec<UntypedAssetId>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum ResourceLoadState {
+    Pending,
+    /// Inserted as a resource.
+    Loaded,
+    Failed(Vec<AssetFailure>),
+}
+
+/// An asset that failed to load, and which resource was waiting for it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetFailure {
+    /// The name of the resource's type, e.g. `DuckAssets`.
+    pub resource: String,
+    pub path: String,
+    pub error: String,
+}
+
+struct TrackedResource {
+    name: String,
+    /// `None` until it's been added.
+    handle: Option<UntypedHandle>,
+    /// Whether to wait for the [`AssetManifest`] before adding it.
+    needs_manifest: bool,
+    state: ResourceLoadState,
+    add: AddResourceAsset,
+    insert: InsertLoadedResource,
+    dependencies: ResourceDependencies,
+}
 
 #[derive(Resource, Default)]
-struct ResourceHandles {
-    // Use a queue for waiting assets so they can be cycled through and moved to
-    // `finished` one at a time.
-    waiting: VecDeque<(UntypedHandle, InsertLoadedResource)>,
-    finished: Vec<UntypedHandle>,
+struct ResourceHandles(Vec<TrackedResource>);
+
+/// How loading every resource asked for with [`LoadResource::load_resource`]
+/// is going.
+#[derive(Resource, Debug, Clone, PartialEq, Default)]
+pub struct LoadingProgress {
+    /// How many of the resources have loaded, all their assets included.
+    pub loaded: usize,
+    /// How many resources there are, not how many assets they hold.
+    pub total: usize,
+    /// The asset we're waiting on, e.g. `audio/sound_effects/step1.ogg`, or
+    /// the resource waiting on it if we can't tell which asset that is yet.
+    pub current: Option<String>,
+    pub failures: Vec<AssetFailure>,
+}
+
+impl LoadingProgress {
+    /// How far along loading is, from `0.0` to `1.0`.
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        self.loaded as f32 / self.total as f32
+    }
+
+    pub fn has_failed(&self) -> bool {
+        !self.failures.is_empty()
+    }
+}
+
+/// Send this to try loading the assets in [`LoadingProgress::failures`] again.
+#[derive(Event, Debug, Default)]
+pub struct RetryFailedAssets;
+
+fn retry_failed_assets(world: &mut World) {
+    if world
+        .resource_mut::<Events<RetryFailedAssets>>()
+        .drain()
+        .count()
+        == 0
+    {
+        return;
+    }
+
+    world.resource_scope(|world, mut resource_handles: Mut<ResourceHandles>| {
+        for resource in &mut resource_handles.0 {
+            if !matches!(resource.state, ResourceLoadState::Failed(_)) {
+                continue;
+            }
+            info!("Retrying loading {}", resource.name);
+            resource.handle = Some((resource.add)(world));
+            resource.state = ResourceLoadState::Pending;
+        }
+    });
 }
 
 fn load_resource_assets(world: &mut World) {
     world.resource_scope(|world, mut resource_handles: Mut<ResourceHandles>| {
-        world.resource_scope(|world, assets: Mut<AssetServer>| {
-            for _ in 0..resource_handles.waiting.len() {
-                let (handle, insert_fn) = resource_handles.waiting.pop_front().unwrap();
-                if assets.is_loaded_with_dependencies(&handle) {
-                    insert_fn(world, &handle);
-                    resource_handles.finished.push(handle);
-                } else {
-                    resource_handles.waiting.push_back((handle, insert_fn));
+        for resource in &mut resource_handles.0 {
+            if resource.state != ResourceLoadState::Pending {
+                continue;
+            }
+
+            let Some(handle) = resource.handle.clone() else {
+                if !resource.needs_manifest || world.contains_resource::<AssetManifest>() {
+                    resource.handle = Some((resource.add)(world));
                 }
+                continue;
+            };
+
+            let asset_server = world.resource::<AssetServer>();
+            if asset_server.is_loaded_with_dependencies(&handle) {
+                (resource.insert)(world, &handle);
+                resource.state = ResourceLoadState::Loaded;
+            } else if asset_server.get_recursive_dependency_load_state(&handle)
+                == Some(RecursiveDependencyLoadState::Failed)
+            {
+                let failures = find_failures(world, resource, &handle);
+                for failure in &failures {
+                    error!(
+                        "{} could not load {}: {}",
+                        failure.resource, failure.path, failure.error
+                    );
+                }
+                resource.state = ResourceLoadState::Failed(failures);
             }
-        });
+        }
+
+        let progress = loading_progress(world, &resource_handles);
+        world.resource_mut::<LoadingProgress>().set_if_neq(progress);
     });
 }
+
+fn loading_progress(world: &World, resource_handles: &ResourceHandles) -> LoadingProgress {
+    let resources = &resource_handles.0;
+    let current = resources
+        .iter()
+        .find(|resource| resource.state == ResourceLoadState::Pending)
+        .map(|resource| {
+            let asset_server = world.resource::<AssetServer>();
+            let dependencies = resource
+                .handle
+                .as_ref()
+                .map_or_else(Vec::new, |handle| (resource.dependencies)(world, handle));
+            dependencies
+                .into_iter()
+                .filter(|&id| {
+                    !matches!(
+                        asset_server.get_load_states(id),
+                        Some((LoadState::Loaded, _, RecursiveDependencyLoadState::Loaded))
+                    )
+                })
+                .find_map(|id| asset_server.get_path(id))
+                .map_or_else(|| resource.name.clone(), |path| path.to_string())
+        });
+
+    LoadingProgress {
+        loaded: resources
+            .iter()
+            .filter(|resource| resource.state == ResourceLoadState::Loaded)
+            .count(),
+        total: resources.len(),
+        current,
+        failures: resources
+            .iter()
+            .filter_map(|resource| match &resource.state {
+                ResourceLoadState::Failed(failures) => Some(failures.iter().cloned()),
+                _ => None,
+            })
+            .flatten()
+            .collect(),
+    }
+}
+
+/// Which of `resource`'s dependencies failed to load, and why.
+fn find_failures(
+    world: &World,
+    resource: &TrackedResource,
+    handle: &UntypedHandle,
+) -> Vec<AssetFailure> {
+    let asset_server = world.resource::<AssetServer>();
+    let failures: Vec<AssetFailure> = (resource.dependencies)(world, handle)
+        .into_iter()
+        .filter_map(|id| {
+            let error = match asset_server.get_load_states(id)? {
+                (LoadState::Failed(error), _, _) => error.to_string(),
+                (_, _, RecursiveDependencyLoadState::Failed) => {
+                    "one of its dependencies failed to load".to_string()
+                }
+                _ => return None,
+            };
+            Some(AssetFailure {
+                resource: resource.name.clone(),
+                path: asset_server
+                    .get_path(id)
+                    .map_or_else(|| format!("{:?}", id), |path| path.to_string()),
+                error,
+            })
+        })
+        .collect();
+
+    if failures.is_empty() {
+        // The resource's own asset couldn't tell us which dependency it was.
+        return vec![AssetFailure {
+            resource: resource.name.clone(),
+            path: resource.name.clone(),
+            error: "one of its assets failed to load".to_string(),
+        }];
+    }
+    failures
+}
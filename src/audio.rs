@@ -1,17 +1,49 @@
-use bevy::prelude::*;
+//! Sound categories and the mixer that sets their volume.
+//!
+//! Every sound is spawned with a [`Music`] or [`SoundEffect`] marker, which
+//! puts it on that [`AudioBus`]. The volume of each playing sound is the
+//! master volume times its bus's volume, as set in [`AudioSettings`], times
+//! its own [`Gain`] if it has one, and is updated live when any of them change.
+//!
+//! Start sounds with [`AudioCommands`], so they always get a marker and spatial
+//! sounds always get a place in the world.
+
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::persistence;
+
+/// Spatial audio uses the distance to attenuate the sound volume. In 2D with the default camera,
+/// 1 pixel is 1 unit of distance, so we use a scale so that 200 pixels is 1 unit of distance for
+/// audio. Sounds closer than that to the listener play at full volume; after that they fall off
+/// with the square of the distance, so a sound 800 pixels away plays at 1/16th volume.
+pub const AUDIO_SCALE: f32 = 1. / 200.0;
+
+/// The key [`AudioSettings`] are saved under.
+pub const AUDIO_SETTINGS_KEY: &str = "audio_settings";
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<AudioSettings>();
+    app.insert_resource(persistence::load::<AudioSettings>(AUDIO_SETTINGS_KEY).unwrap_or_default());
+
+    // Sinks are created in `PostUpdate` when queued sounds start playing, so
+    // set their volume right after that, before anyone hears them for long.
+    app.add_systems(Last, apply_audio_settings);
+}
 
 /// An organizational marker component that should be added to a spawned [`AudioBundle`] if it is in the
 /// general "music" category (ex: global background music, soundtrack, etc).
 ///
-/// This can then be used to query for and operate on sounds in that category. For example:
+/// Its volume follows [`AudioBus::Music`]. It can also be used to query for and operate on
+/// sounds in that category. For example:
 ///
-/// ```
+/// ```no_run
 /// use bevy::prelude::*;
-/// use ducks_test::audio::Music;
+/// use quackers_beta::audio::Music;
 ///
-/// fn set_music_volume(sink_query: Query<&AudioSink, With<Music>>) {
+/// fn pause_music(sink_query: Query<&AudioSink, With<Music>>) {
 ///     for sink in &sink_query {
-///         sink.set_volume(0.5);
+///         sink.pause();
 ///     }
 /// }
 /// ```
@@ -21,17 +53,244 @@ pub struct Music;
 /// An organizational marker component that should be added to a spawned [`AudioBundle`] if it is in the
 /// general "sound effect" category (ex: footsteps, the sound of a magic spell, a door opening).
 ///
-/// This can then be used to query for and operate on sounds in that category. For example:
+/// Its volume follows [`AudioBus::SoundEffects`]. It can also be used to query for and operate on
+/// sounds in that category. For example:
 ///
-/// ```
+/// ```no_run
 /// use bevy::prelude::*;
-/// use ducks_test::audio::SoundEffect;
+/// use quackers_beta::audio::SoundEffect;
 ///
-/// fn set_sound_effect_volume(sink_query: Query<&AudioSink, With<SoundEffect>>) {
+/// fn stop_sound_effects(sink_query: Query<&AudioSink, With<SoundEffect>>) {
 ///     for sink in &sink_query {
-///         sink.set_volume(0.5);
+///         sink.stop();
 ///     }
 /// }
 /// ```
 #[derive(Component, Default)]
 pub struct SoundEffect;
+
+/// Scales the volume of one playing sound on top of its bus, e.g. to fade it
+/// in or out. Change it to change how loud the sound is right now.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Gain(pub f32);
+
+impl Default for Gain {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioBus {
+    /// Applies to every sound.
+    Master,
+    Music,
+    SoundEffects,
+}
+
+impl AudioBus {
+    pub const ALL: [AudioBus; 3] = [AudioBus::Master, AudioBus::Music, AudioBus::SoundEffects];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AudioBus::Master => "Master",
+            AudioBus::Music => "Music",
+            AudioBus::SoundEffects => "Sound effects",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Reflect)]
+pub struct BusSettings {
+    /// From `0.0` (silent) to `1.0` (as loud as the sound was made).
+    pub volume: f32,
+    pub muted: bool,
+}
+
+impl Default for BusSettings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl BusSettings {
+    fn gain(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.volume.clamp(0.0, 1.0)
+        }
+    }
+}
+
+/// The player's volume settings, saved between sessions.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, Reflect)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master: BusSettings,
+    pub music: BusSettings,
+    pub sound_effects: BusSettings,
+}
+
+impl AudioSettings {
+    pub fn bus(&self, bus: AudioBus) -> &BusSettings {
+        match bus {
+            AudioBus::Master => &self.master,
+            AudioBus::Music => &self.music,
+            AudioBus::SoundEffects => &self.sound_effects,
+        }
+    }
+
+    pub fn bus_mut(&mut self, bus: AudioBus) -> &mut BusSettings {
+        match bus {
+            AudioBus::Master => &mut self.master,
+            AudioBus::Music => &mut self.music,
+            AudioBus::SoundEffects => &mut self.sound_effects,
+        }
+    }
+
+    /// How loud sounds on `bus` play, including the master volume.
+    pub fn gain(&self, bus: AudioBus) -> f32 {
+        let bus_gain = match bus {
+            AudioBus::Master => 1.0,
+            bus => self.bus(bus).gain(),
+        };
+        self.master.gain() * bus_gain
+    }
+}
+
+fn apply_audio_settings(
+    settings: Res<AudioSettings>,
+    global_volume: Res<GlobalVolume>,
+    sounds: Query<(
+        &PlaybackSettings,
+        Option<Ref<AudioSink>>,
+        Option<Ref<SpatialAudioSink>>,
+        Option<Ref<Gain>>,
+        Has<Music>,
+        Has<SoundEffect>,
+    )>,
+) {
+    for (playback_settings, sink, spatial_sink, gain, is_music, is_sound_effect) in &sounds {
+        let is_new = sink.as_ref().is_some_and(Ref::is_added)
+            || spatial_sink.as_ref().is_some_and(Ref::is_added);
+        let gain_changed = gain.as_ref().is_some_and(Ref::is_changed);
+        if !is_new && !gain_changed && !settings.is_changed() {
+            continue;
+        }
+        if is_new && !is_music && !is_sound_effect {
+            warn!("Playing a sound without a Music or SoundEffect marker, treating it as a sound effect");
+        }
+
+        let bus = if is_music {
+            AudioBus::Music
+        } else {
+            AudioBus::SoundEffects
+        };
+        let gain = gain.map_or(1.0, |gain| gain.0.max(0.0));
+        let volume =
+            playback_settings.volume.get() * global_volume.volume.get() * settings.gain(bus) * gain;
+        if let Some(sink) = sink {
+            sink.set_volume(volume);
+        }
+        if let Some(spatial_sink) = spatial_sink {
+            spatial_sink.set_volume(volume);
+        }
+    }
+}
+
+/// A single way to start sounds, for every module.
+pub trait AudioCommands {
+    /// Play music. The music director in `music.rs` is the only one who should.
+    fn play_music(&mut self, sound: AudioBundle) -> EntityCommands<'_>;
+
+    /// Play a sound effect that sounds the same wherever you are, like our own quack.
+    fn play_sound_effect(&mut self, sound: AudioBundle) -> EntityCommands<'_>;
+
+    /// Play a sound effect from a fixed spot in the world.
+    fn play_sound_effect_at(&mut self, position: Vec2, sound: AudioBundle) -> EntityCommands<'_>;
+
+    /// Play a sound effect from `emitter`, following it as it moves. The
+    /// sound stops if `emitter` is despawned.
+    fn play_sound_effect_on(&mut self, emitter: Entity, sound: AudioBundle) -> EntityCommands<'_>;
+}
+
+impl AudioCommands for Commands<'_, '_> {
+    fn play_music(&mut self, sound: AudioBundle) -> EntityCommands<'_> {
+        self.spawn((sound, Music))
+    }
+
+    fn play_sound_effect(&mut self, sound: AudioBundle) -> EntityCommands<'_> {
+        self.spawn((sound, SoundEffect))
+    }
+
+    fn play_sound_effect_at(&mut self, position: Vec2, sound: AudioBundle) -> EntityCommands<'_> {
+        self.spawn((
+            Name::new("Sound effect"),
+            // On the same plane as the listener, so only the distance across
+            // the screen makes it quieter.
+            TransformBundle::from_transform(Transform::from_translation(position.extend(0.0))),
+            spatial(sound),
+            SoundEffect,
+        ))
+    }
+
+    fn play_sound_effect_on(&mut self, emitter: Entity, sound: AudioBundle) -> EntityCommands<'_> {
+        let mut sound = self.spawn((
+            Name::new("Sound effect"),
+            TransformBundle::default(),
+            spatial(sound),
+            SoundEffect,
+        ));
+        sound.set_parent(emitter);
+        sound
+    }
+}
+
+fn spatial(sound: AudioBundle) -> AudioBundle {
+    AudioBundle {
+        settings: sound.settings.with_spatial(true),
+        ..sound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bus_gain_includes_the_master_volume() {
+        let mut settings = AudioSettings::default();
+        settings.master.volume = 0.5;
+        settings.music.volume = 0.5;
+        assert_eq!(settings.gain(AudioBus::Master), 0.5);
+        assert_eq!(settings.gain(AudioBus::Music), 0.25);
+        assert_eq!(settings.gain(AudioBus::SoundEffects), 0.5);
+    }
+
+    #[test]
+    fn muting_silences_a_bus_or_everything() {
+        let mut settings = AudioSettings::default();
+        settings.sound_effects.muted = true;
+        assert_eq!(settings.gain(AudioBus::SoundEffects), 0.0);
+        assert_eq!(settings.gain(AudioBus::Music), 1.0);
+
+        settings.master.muted = true;
+        for bus in AudioBus::ALL {
+            assert_eq!(settings.gain(bus), 0.0);
+        }
+    }
+
+    #[test]
+    fn volumes_out_of_range_are_clamped() {
+        let mut settings = AudioSettings::default();
+        settings.master.volume = 3.0;
+        settings.music.volume = -1.0;
+        assert_eq!(settings.gain(AudioBus::Master), 1.0);
+        assert_eq!(settings.gain(AudioBus::Music), 0.0);
+    }
+}
@@ -0,0 +1,435 @@
+//! Data-driven sprite sheet animation.
+//!
+//! An [`AnimationLibrary`] is a `.anim.json` asset in `assets/animations` that
+//! says how a sprite sheet is cut into frames and which clips it has: the
+//! frames to show, how long each one lasts, what happens at the end, and named
+//! events to fire on particular frames (like `"footstep"`).
+//!
+//! Entities with a [`SpriteAnimator`] play one clip of a library at a time,
+//! falling back to their idle clip when a clip that doesn't loop is done. They
+//! get their [`TextureAtlas`] automatically, sharing one layout per library
+//! (and per offset, for sprite sheets packed into a bigger atlas).
+
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::Deserialize;
+
+use crate::{json_asset::RegisterJsonAsset, AppSet};
+
+use super::interest::OutOfView;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_json_asset::<AnimationLibrary>(&["anim.json"]);
+    app.init_resource::<AtlasLayoutCache>();
+    app.add_event::<AnimationEvent>();
+
+    app.add_systems(Update, tick_animations.in_set(AppSet::TickTimers));
+    // After gameplay has picked this frame's clips, and before rendering.
+    app.add_systems(
+        PostUpdate,
+        (forget_changed_layouts, attach_atlas_layouts, update_animation_atlas).chain(),
+    );
+}
+
+/// A sprite sheet and the clips that can be played from it.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct AnimationLibrary {
+    pub atlas: AtlasGrid,
+    pub clips: HashMap<String, AnimationClip>,
+}
+
+/// How the sprite sheet is cut into equally sized frames. Frames are numbered
+/// left to right, top to bottom, starting at `0`.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct AtlasGrid {
+    pub tile_size: UVec2,
+    pub columns: u32,
+    pub rows: u32,
+    /// Space between frames.
+    #[serde(default)]
+    pub padding: Option<UVec2>,
+    /// Space before the first frame.
+    #[serde(default)]
+    pub offset: Option<UVec2>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AnimationClip {
+    /// The frames of the sprite sheet to show, in order.
+    pub frames: Vec<usize>,
+    /// How long each frame is shown.
+    pub frame_duration_ms: u64,
+    /// Overrides `frame_duration_ms` for each frame, if given.
+    #[serde(default)]
+    pub frame_durations_ms: Vec<u64>,
+    #[serde(default)]
+    pub mode: LoopMode,
+    #[serde(default)]
+    pub events: Vec<ClipEvent>,
+}
+
+impl AnimationClip {
+    fn frame_duration(&self, frame: usize) -> Duration {
+        let millis = self
+            .frame_durations_ms
+            .get(frame)
+            .copied()
+            .unwrap_or(self.frame_duration_ms);
+        // A zero duration would never let the clip finish its tick.
+        Duration::from_millis(millis.max(1))
+    }
+
+    fn atlas_index(&self, frame: usize) -> usize {
+        self.frames.get(frame).copied().unwrap_or_default()
+    }
+}
+
+/// What a clip does after its last frame.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LoopMode {
+    /// Start again from the first frame.
+    #[default]
+    Loop,
+    /// Go back to the animator's idle clip.
+    Once,
+    /// Stay on the last frame.
+    Hold,
+}
+
+/// Fire an [`AnimationEvent`] called `name` whenever the clip moves on to
+/// `frame` (an index into the clip's `frames`).
+#[derive(Deserialize, Debug, Clone)]
+pub struct ClipEvent {
+    pub frame: usize,
+    pub name: String,
+}
+
+/// Sent when an animated entity reaches a frame with a [`ClipEvent`].
+#[derive(Event, Debug, Clone)]
+pub struct AnimationEvent {
+    pub entity: Entity,
+    pub name: String,
+}
+
+/// Plays clips from an [`AnimationLibrary`] on a sprite.
+#[derive(Component, Debug)]
+pub struct SpriteAnimator {
+    library: Handle<AnimationLibrary>,
+    /// Played to start with, and whenever a clip that doesn't loop is done.
+    idle_clip: String,
+    clip: String,
+    /// Index into the clip's `frames`.
+    frame: usize,
+    /// How long the current frame has been shown.
+    elapsed: Duration,
+    /// How many more times to play the clip, if limited.
+    loops_left: Option<u32>,
+    /// Whether a holding clip has reached its last frame.
+    finished: bool,
+    /// Where the sprite sheet starts in its image, added to the library's own offset.
+    atlas_offset: UVec2,
+}
+
+impl SpriteAnimator {
+    pub fn new(library: Handle<AnimationLibrary>, idle_clip: &str) -> Self {
+        Self {
+            library,
+            idle_clip: idle_clip.to_string(),
+            clip: idle_clip.to_string(),
+            frame: 0,
+            elapsed: Duration::ZERO,
+            loops_left: None,
+            finished: false,
+            atlas_offset: UVec2::ZERO,
+        }
+    }
+
+    /// Play a sprite sheet that starts at `offset` in its image, e.g. because
+    /// it was packed into an atlas with other images.
+    pub fn with_atlas_offset(mut self, offset: UVec2) -> Self {
+        self.atlas_offset = offset;
+        self
+    }
+
+    /// Play `clip`, unless it's already playing.
+    pub fn play(&mut self, clip: &str) {
+        if self.clip != clip {
+            self.start(clip.to_string());
+        }
+        self.loops_left = None;
+    }
+
+    /// Play `clip` `loops` times, then go back to the idle clip. If it's
+    /// already playing, it carries on and plays `loops` more times from here.
+    pub fn play_loops(&mut self, clip: &str, loops: u32) {
+        if self.clip != clip {
+            self.start(clip.to_string());
+        }
+        self.loops_left = Some(loops);
+    }
+
+    fn start(&mut self, clip: String) {
+        self.clip = clip;
+        self.frame = 0;
+        self.elapsed = Duration::ZERO;
+        self.loops_left = None;
+        self.finished = false;
+    }
+
+    /// Move on by `delta`, calling `on_frame` for each frame that is reached.
+    fn advance(
+        &mut self,
+        library: &AnimationLibrary,
+        delta: Duration,
+        mut on_frame: impl FnMut(&AnimationClip, usize),
+    ) {
+        let Some(clip) = library.clips.get(&self.clip) else {
+            return;
+        };
+        if self.finished || clip.frames.is_empty() {
+            return;
+        }
+
+        self.elapsed += delta;
+        loop {
+            let duration = clip.frame_duration(self.frame);
+            if self.elapsed < duration {
+                return;
+            }
+            self.elapsed -= duration;
+
+            if self.frame + 1 < clip.frames.len() {
+                self.frame += 1;
+                on_frame(clip, self.frame);
+                continue;
+            }
+
+            // The end of the clip.
+            self.loops_left = self.loops_left.map(|loops| loops.saturating_sub(1));
+            let repeat = match self.loops_left {
+                Some(loops) => loops > 0,
+                None => clip.mode == LoopMode::Loop,
+            };
+            if repeat {
+                self.frame = 0;
+                on_frame(clip, self.frame);
+            } else if clip.mode == LoopMode::Hold {
+                self.finished = true;
+                return;
+            } else {
+                let idle_clip = self.idle_clip.clone();
+                self.start(idle_clip);
+                return;
+            }
+        }
+    }
+}
+
+/// One [`TextureAtlasLayout`] per library and atlas offset, shared by
+/// everything animated with it.
+#[derive(Resource, Debug, Default)]
+struct AtlasLayoutCache(HashMap<(AssetId<AnimationLibrary>, UVec2), Handle<TextureAtlasLayout>>);
+
+impl AtlasLayoutCache {
+    fn get_or_insert(
+        &mut self,
+        id: AssetId<AnimationLibrary>,
+        atlas_offset: UVec2,
+        library: &AnimationLibrary,
+        layouts: &mut Assets<TextureAtlasLayout>,
+    ) -> Handle<TextureAtlasLayout> {
+        self.0
+            .entry((id, atlas_offset))
+            .or_insert_with(|| {
+                let grid = library.atlas;
+                layouts.add(TextureAtlasLayout::from_grid(
+                    grid.tile_size,
+                    grid.columns,
+                    grid.rows,
+                    grid.padding,
+                    Some(grid.offset.unwrap_or_default() + atlas_offset),
+                ))
+            })
+            .clone()
+    }
+}
+
+fn tick_animations(
+    time: Res<Time>,
+    libraries: Res<Assets<AnimationLibrary>>,
+    mut animators: Query<(Entity, &mut SpriteAnimator), Without<OutOfView>>,
+    mut animation_events: EventWriter<AnimationEvent>,
+) {
+    for (entity, mut animator) in &mut animators {
+        let Some(library) = libraries.get(&animator.library) else {
+            continue;
+        };
+        animator.advance(library, time.delta(), |clip, frame| {
+            for event in clip.events.iter().filter(|event| event.frame == frame) {
+                animation_events.send(AnimationEvent {
+                    entity,
+                    name: event.name.clone(),
+                });
+            }
+        });
+    }
+}
+
+/// Rebuild the layout of a library that was edited while the game runs.
+fn forget_changed_layouts(
+    mut library_events: EventReader<AssetEvent<AnimationLibrary>>,
+    mut cache: ResMut<AtlasLayoutCache>,
+    mut animated: Query<(&SpriteAnimator, &mut TextureAtlas)>,
+    libraries: Res<Assets<AnimationLibrary>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    for event in library_events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+        cache.0.retain(|(library_id, _), _| library_id != id);
+        let Some(library) = libraries.get(*id) else {
+            continue;
+        };
+        for (animator, mut atlas) in &mut animated {
+            if animator.library.id() == *id {
+                atlas.layout =
+                    cache.get_or_insert(*id, animator.atlas_offset, library, &mut layouts);
+            }
+        }
+    }
+}
+
+fn attach_atlas_layouts(
+    mut commands: Commands,
+    mut cache: ResMut<AtlasLayoutCache>,
+    libraries: Res<Assets<AnimationLibrary>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    new_animators: Query<(Entity, &SpriteAnimator), Without<TextureAtlas>>,
+) {
+    for (entity, animator) in &new_animators {
+        let Some(library) = libraries.get(&animator.library) else {
+            continue;
+        };
+        let index = library
+            .clips
+            .get(&animator.clip)
+            .map_or(0, |clip| clip.atlas_index(animator.frame));
+        let layout = cache.get_or_insert(
+            animator.library.id(),
+            animator.atlas_offset,
+            library,
+            &mut layouts,
+        );
+        commands.entity(entity).insert(TextureAtlas { layout, index });
+    }
+}
+
+fn update_animation_atlas(
+    libraries: Res<Assets<AnimationLibrary>>,
+    mut animated: Query<(&SpriteAnimator, &mut TextureAtlas)>,
+) {
+    for (animator, mut atlas) in &mut animated {
+        let Some(clip) = libraries
+            .get(&animator.library)
+            .and_then(|library| library.clips.get(&animator.clip))
+        else {
+            continue;
+        };
+        let index = clip.atlas_index(animator.frame);
+        if atlas.index != index {
+            atlas.index = index;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(frames: usize, mode: LoopMode) -> AnimationClip {
+        AnimationClip {
+            frames: (0..frames).collect(),
+            frame_duration_ms: 100,
+            frame_durations_ms: Vec::new(),
+            mode,
+            events: Vec::new(),
+        }
+    }
+
+    fn library() -> AnimationLibrary {
+        let mut clips = HashMap::default();
+        clips.insert("idle".to_string(), clip(2, LoopMode::Loop));
+        clips.insert("walk".to_string(), clip(3, LoopMode::Loop));
+        clips.insert("wave".to_string(), clip(2, LoopMode::Once));
+        clips.insert("sit".to_string(), clip(2, LoopMode::Hold));
+        AnimationLibrary {
+            atlas: AtlasGrid {
+                tile_size: UVec2::splat(16),
+                columns: 3,
+                rows: 1,
+                padding: None,
+                offset: None,
+            },
+            clips,
+        }
+    }
+
+    /// Advances by `millis` and returns the frames reached on the way.
+    fn advance(
+        animator: &mut SpriteAnimator,
+        library: &AnimationLibrary,
+        millis: u64,
+    ) -> Vec<usize> {
+        let mut reached = Vec::new();
+        animator.advance(library, Duration::from_millis(millis), |_, frame| {
+            reached.push(frame)
+        });
+        reached
+    }
+
+    #[test]
+    fn frames_move_on_once_their_duration_has_passed() {
+        let library = library();
+        let mut animator = SpriteAnimator::new(Handle::default(), "walk");
+        assert_eq!(advance(&mut animator, &library, 99), Vec::<usize>::new());
+        assert_eq!(advance(&mut animator, &library, 1), vec![1]);
+        // A long tick reaches every frame it passes.
+        assert_eq!(advance(&mut animator, &library, 250), vec![2, 0]);
+        assert_eq!(animator.elapsed, Duration::from_millis(50));
+    }
+
+    #[test]
+    fn once_clips_go_back_to_idle() {
+        let library = library();
+        let mut animator = SpriteAnimator::new(Handle::default(), "idle");
+        animator.play("wave");
+        advance(&mut animator, &library, 200);
+        assert_eq!(animator.clip, "idle");
+        assert_eq!(animator.frame, 0);
+    }
+
+    #[test]
+    fn hold_clips_stay_on_their_last_frame() {
+        let library = library();
+        let mut animator = SpriteAnimator::new(Handle::default(), "idle");
+        animator.play("sit");
+        advance(&mut animator, &library, 1000);
+        assert_eq!(animator.clip, "sit");
+        assert_eq!(animator.frame, 1);
+        assert!(animator.finished);
+    }
+
+    #[test]
+    fn limited_loops_go_back_to_idle_when_done() {
+        let library = library();
+        let mut animator = SpriteAnimator::new(Handle::default(), "idle");
+        animator.play_loops("walk", 2);
+        advance(&mut animator, &library, 300);
+        assert_eq!((animator.clip.as_str(), animator.frame), ("walk", 0));
+        advance(&mut animator, &library, 300);
+        assert_eq!((animator.clip.as_str(), animator.frame), ("idle", 0));
+    }
+}
@@ -0,0 +1,387 @@
+//! Auth tokens for the join handshake, so the server can tie a duck (and its
+//! score) to an account instead of trusting whatever friendly name we send.
+//!
+//! A token can come from:
+//! - the page URL on web, as `#token=...` or `?token=...`,
+//! - the `--token <token>` CLI arg or `QUACKERS_AUTH_TOKEN` env var on native,
+//! - a login endpoint, configured with `?login=...` on web or
+//!   `--login-endpoint <url>` / `QUACKERS_LOGIN_ENDPOINT` on native.
+//!
+//! When a login endpoint is configured, a fresh token is fetched every time we
+//! (re)connect, passing along the previous token so the endpoint can refresh it.
+//! An endpoint that takes longer than [`LOGIN_TIMEOUT`] to answer is given up on.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use bevy::{prelude::*, utils::Instant};
+use serde::Deserialize;
+
+use crate::screens::Screen;
+
+use super::websocket_connect::WebSocketConnectionEvents;
+
+/// How long the login endpoint gets to answer before we join without it.
+pub const LOGIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(AuthSettings::from_environment());
+    app.init_resource::<AuthToken>();
+    app.init_resource::<LastConnectionError>();
+    app.add_event::<AuthRejectedBevyEvent>();
+
+    app.add_systems(Startup, use_static_token);
+    app.add_systems(
+        Update,
+        (
+            refresh_token_on_connect,
+            poll_token_fetch,
+            auth_rejected_handler,
+        ),
+    );
+}
+
+/// Where our auth token comes from.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct AuthSettings {
+    pub static_token: Option<String>,
+    pub login_endpoint: Option<String>,
+}
+
+/// The token sent along with the join request.
+#[derive(Resource, Debug, Default)]
+pub struct AuthToken {
+    pub token: Option<String>,
+    fetch: Option<(TokenFetch, Instant)>,
+}
+
+impl AuthToken {
+    /// Whether we are still waiting on the login endpoint. The join request
+    /// holds off until this is `false`.
+    pub fn is_fetching(&self) -> bool {
+        self.fetch.is_some()
+    }
+}
+
+/// The result of an in-flight login request, filled in by a background task.
+type TokenFetch = Arc<Mutex<Option<Result<String, String>>>>;
+
+/// Why we last failed to connect, either because the server (or login endpoint)
+/// turned us away or because we couldn't reach it. Shown on the server select
+/// screen.
+#[derive(Resource, Debug, Default)]
+pub struct LastConnectionError(pub Option<String>);
+
+#[derive(Event, Debug, Clone, Deserialize)]
+pub struct AuthRejectedBevyEvent {
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoginResponseData {
+    token: String,
+}
+
+impl AuthSettings {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn from_environment() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let non_empty = |value: String| Some(value).filter(|value| !value.is_empty());
+        Self {
+            static_token: cli_arg(&args, "--token")
+                .or_else(|| std::env::var("QUACKERS_AUTH_TOKEN").ok())
+                .and_then(non_empty),
+            login_endpoint: cli_arg(&args, "--login-endpoint")
+                .or_else(|| std::env::var("QUACKERS_LOGIN_ENDPOINT").ok())
+                .and_then(non_empty),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn from_environment() -> Self {
+        let Some(location) = web_sys::window().map(|window| window.location()) else {
+            return Self::default();
+        };
+        let param = |source: Option<String>, name: &str| {
+            let source = source?;
+            let params = web_sys::UrlSearchParams::new_with_str(&source).ok()?;
+            params.get(name).filter(|value| !value.is_empty())
+        };
+        // The fragment never reaches the server hosting the page, so prefer it for tokens.
+        let hash = location
+            .hash()
+            .ok()
+            .map(|hash| hash.trim_start_matches('#').to_string());
+        let search = location.search().ok();
+        Self {
+            static_token: param(hash.clone(), "token").or_else(|| param(search.clone(), "token")),
+            login_endpoint: param(search, "login").or_else(|| param(hash, "login")),
+        }
+    }
+}
+
+/// Reads `--name value` or `--name=value` from the command line.
+#[cfg(not(target_arch = "wasm32"))]
+fn cli_arg(args: &[String], name: &str) -> Option<String> {
+    let prefix = format!("{name}=");
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == name {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix(&prefix).map(|value| value.to_string())
+        }
+    })
+}
+
+fn use_static_token(settings: Res<AuthSettings>, mut auth_token: ResMut<AuthToken>) {
+    if settings.static_token.is_some() {
+        info!("Using auth token from launch settings");
+        auth_token.token = settings.static_token.clone();
+    }
+}
+
+/// Ask the login endpoint for a fresh token whenever a connection is set up.
+fn refresh_token_on_connect(
+    mut ev_connect: EventReader<WebSocketConnectionEvents>,
+    settings: Res<AuthSettings>,
+    mut auth_token: ResMut<AuthToken>,
+) {
+    for _ev in ev_connect.read() {
+        let Some(login_endpoint) = settings.login_endpoint.clone() else {
+            continue;
+        };
+        if auth_token.is_fetching() {
+            continue;
+        }
+
+        info!("Fetching auth token from {}", login_endpoint);
+        let fetch = TokenFetch::default();
+        spawn_login_request(login_endpoint, auth_token.token.clone(), fetch.clone());
+        auth_token.fetch = Some((fetch, Instant::now()));
+    }
+}
+
+fn poll_token_fetch(
+    mut auth_token: ResMut<AuthToken>,
+    mut last_connection_error: ResMut<LastConnectionError>,
+    settings: Res<AuthSettings>,
+) {
+    let Some((fetch, started)) = auth_token.fetch.clone() else {
+        return;
+    };
+    let result = match fetch.lock().unwrap().take() {
+        Some(result) => result,
+        // Whatever it answers after this goes nowhere.
+        None if started.elapsed() > LOGIN_TIMEOUT => {
+            Err("the login endpoint took too long to answer".to_string())
+        }
+        None => return,
+    };
+    auth_token.fetch = None;
+
+    match result {
+        Ok(token) => {
+            info!("Got a fresh auth token");
+            auth_token.token = Some(token);
+        }
+        Err(e) => {
+            // Fall back to whatever token we were launched with, if any.
+            warn!("Login request failed: {e}");
+            auth_token.token = settings.static_token.clone();
+            last_connection_error.0 = Some(format!("Could not sign in: {e}"));
+        }
+    }
+}
+
+fn auth_rejected_handler(
+    mut event_reader: EventReader<AuthRejectedBevyEvent>,
+    mut auth_token: ResMut<AuthToken>,
+    mut last_connection_error: ResMut<LastConnectionError>,
+    settings: Res<AuthSettings>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    for e in event_reader.read() {
+        warn!("Server rejected our auth token: {}", e.reason);
+
+        // A token from the login endpoint is replaced on the next connect anyway,
+        // so only keep the one we were launched with.
+        auth_token.token = settings.static_token.clone();
+        last_connection_error.0 = Some(format!("Sign-in rejected: {}", e.reason));
+
+        // Leaving gameplay closes the socket.
+        next_screen.set(Screen::ServerSelect);
+    }
+}
+
+fn build_login_request_body() -> String {
+    serde_json::json!({ "friendly_name": "foo" }).to_string()
+}
+
+fn parse_login_response(body: &str) -> Result<String, String> {
+    serde_json::from_str::<LoginResponseData>(body)
+        .map(|data| data.token)
+        .map_err(|e| format!("unexpected login response ({e})"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn spawn_login_request(login_endpoint: String, previous_token: Option<String>, fetch: TokenFetch) {
+    bevy::tasks::AsyncComputeTaskPool::get()
+        .spawn(async move {
+            let result = native_http::post_json(
+                &login_endpoint,
+                previous_token.as_deref(),
+                &build_login_request_body(),
+            )
+            .and_then(|body| parse_login_response(&body));
+            *fetch.lock().unwrap() = Some(result);
+        })
+        .detach();
+}
+
+#[cfg(target_arch = "wasm32")]
+fn spawn_login_request(login_endpoint: String, previous_token: Option<String>, fetch: TokenFetch) {
+    wasm_bindgen_futures::spawn_local(async move {
+        let result = web_fetch::post_json(
+            &login_endpoint,
+            previous_token.as_deref(),
+            &build_login_request_body(),
+        )
+        .await
+        .and_then(|body| parse_login_response(&body));
+        *fetch.lock().unwrap() = Some(result);
+    });
+}
+
+/// Just enough of an HTTP/1.0 client to POST to the login endpoint.
+#[cfg(not(target_arch = "wasm32"))]
+mod native_http {
+    use std::{
+        io::{Read, Write},
+        net::{TcpStream, ToSocketAddrs},
+        sync::Arc,
+    };
+
+    use super::LOGIN_TIMEOUT as TIMEOUT;
+
+    pub fn post_json(url: &str, bearer: Option<&str>, body: &str) -> Result<String, String> {
+        let uri: http::Uri = url.parse().map_err(|e| format!("bad login url ({e})"))?;
+        let host = uri.host().ok_or("login url has no host")?.to_string();
+        let https = match uri.scheme_str() {
+            Some("https") => true,
+            Some("http") => false,
+            _ => return Err("login url must be http:// or https://".to_string()),
+        };
+        let port = uri.port_u16().unwrap_or(if https { 443 } else { 80 });
+        let path = uri.path_and_query().map_or("/", |p| p.as_str());
+
+        let mut request = format!(
+            "POST {path} HTTP/1.0\r\nHost: {host}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n",
+            body.len()
+        );
+        if let Some(token) = bearer {
+            request.push_str(&format!("Authorization: Bearer {token}\r\n"));
+        }
+        request.push_str("\r\n");
+        request.push_str(body);
+
+        let address = (host.as_str(), port)
+            .to_socket_addrs()
+            .map_err(|e| e.to_string())?
+            .next()
+            .ok_or("could not resolve the login url's host")?;
+        let tcp = TcpStream::connect_timeout(&address, TIMEOUT).map_err(|e| e.to_string())?;
+        tcp.set_read_timeout(Some(TIMEOUT)).map_err(|e| e.to_string())?;
+        tcp.set_write_timeout(Some(TIMEOUT)).map_err(|e| e.to_string())?;
+
+        let response = if https {
+            let roots = rustls::RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.into(),
+            };
+            let config = rustls::ClientConfig::builder()
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+            let server_name = rustls::pki_types::ServerName::try_from(host.clone())
+                .map_err(|e| e.to_string())?;
+            let connection = rustls::ClientConnection::new(Arc::new(config), server_name)
+                .map_err(|e| e.to_string())?;
+            exchange(rustls::StreamOwned::new(connection, tcp), &request)?
+        } else {
+            exchange(tcp, &request)?
+        };
+
+        parse_response(&response)
+    }
+
+    fn exchange(mut stream: impl Read + Write, request: &str) -> Result<Vec<u8>, String> {
+        stream
+            .write_all(request.as_bytes())
+            .map_err(|e| e.to_string())?;
+        let mut response = Vec::new();
+        match stream.read_to_end(&mut response) {
+            Ok(_) => Ok(response),
+            // Some servers skip the TLS close_notify, the body is still complete.
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof && !response.is_empty() => {
+                Ok(response)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn parse_response(response: &[u8]) -> Result<String, String> {
+        let mut headers = [httparse::EMPTY_HEADER; 32];
+        let mut parsed = httparse::Response::new(&mut headers);
+        let body_start = match parsed.parse(response) {
+            Ok(httparse::Status::Complete(body_start)) => body_start,
+            Ok(httparse::Status::Partial) => return Err("incomplete login response".to_string()),
+            Err(e) => return Err(format!("bad login response ({e})")),
+        };
+        match parsed.code {
+            Some(200..=299) => {
+                Ok(String::from_utf8_lossy(&response[body_start..]).into_owned())
+            }
+            Some(code) => Err(format!("login endpoint answered {code}")),
+            None => Err("login endpoint sent no status".to_string()),
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web_fetch {
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_futures::JsFuture;
+    use web_sys::{Request, RequestInit, Response};
+
+    pub async fn post_json(url: &str, bearer: Option<&str>, body: &str) -> Result<String, String> {
+        let describe = |e: JsValue| format!("{e:?}");
+
+        let init = RequestInit::new();
+        init.set_method("POST");
+        init.set_body(&JsValue::from_str(body));
+        let request = Request::new_with_str_and_init(url, &init).map_err(describe)?;
+        let headers = request.headers();
+        headers
+            .set("Content-Type", "application/json")
+            .map_err(describe)?;
+        if let Some(token) = bearer {
+            headers
+                .set("Authorization", &format!("Bearer {token}"))
+                .map_err(describe)?;
+        }
+
+        let window = web_sys::window().ok_or("no window")?;
+        let response: Response = JsFuture::from(window.fetch_with_request(&request))
+            .await
+            .map_err(describe)?
+            .dyn_into()
+            .map_err(describe)?;
+        if !response.ok() {
+            return Err(format!("login endpoint answered {}", response.status()));
+        }
+        let text = JsFuture::from(response.text().map_err(describe)?)
+            .await
+            .map_err(describe)?;
+        text.as_string().ok_or_else(|| "login response was not text".to_string())
+    }
+}
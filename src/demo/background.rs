@@ -1,86 +1,324 @@
+//! The level's decorations: the grass, the trees around the edges, reeds,
+//! ripples and clouds.
+//!
+//! Decorations can scroll at a different speed than the camera ([`Parallax`])
+//! to look nearer or further away, float across the level ([`Drift`]), play
+//! a sprite sheet ([`DecorationAnimation`]) and sway in the wind ([`Sway`]).
+//! All of them are tinted by the time of day on the [`WorldClock`].
+
+use std::{f32::consts::TAU, time::Duration};
+
 use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{asset_manifest::AssetManifest, screens::Screen, AppSet};
+
+use super::{
+    camera::{update_camera, CameraController},
+    level::{DecorationSpriteSheet, DecorationSway, LevelDecoration},
+    movement::WorldBounds,
+};
 
-use crate::demo::movement::{MAX_X_POS, MIN_X_POS, MIN_Y_POS};
+/// How long a whole day takes, in real time.
+const DAY_LENGTH: Duration = Duration::from_secs(8 * 60);
+/// The hour the clock starts at when the game starts.
+const START_HOUR: f32 = 9.0;
+
+/// How far past the world bounds drifting decorations go before wrapping.
+const DRIFT_MARGIN: f32 = 600.0;
+
+/// The tint at each hour, blended in between. Must start at `0.0` and end at `24.0`.
+const TINT_KEYFRAMES: [(f32, Color); 8] = [
+    (0.0, NIGHT_TINT),
+    (5.0, NIGHT_TINT),
+    (7.0, Color::srgb(1.0, 0.82, 0.72)),
+    (9.0, Color::WHITE),
+    (17.0, Color::WHITE),
+    (19.0, Color::srgb(1.0, 0.72, 0.58)),
+    (21.0, NIGHT_TINT),
+    (24.0, NIGHT_TINT),
+];
+const NIGHT_TINT: Color = Color::srgb(0.38, 0.42, 0.68);
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(Startup, create_background);
+    app.register_type::<WorldClock>();
+    app.init_resource::<WorldClock>();
+
+    app.add_systems(
+        Update,
+        (
+            (tick_world_clock, tick_decoration_animations).in_set(AppSet::TickTimers),
+            (
+                (drift_decorations, apply_parallax, sway_decorations)
+                    .chain()
+                    .after(update_camera),
+                animate_decorations,
+                apply_day_night_tint,
+            )
+                .in_set(AppSet::Update),
+        )
+            .run_if(in_state(Screen::Gameplay)),
+    );
 }
 
-fn create_background(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-) {
-    // let texture_handle = asset_server.load("images/ducks-bg-test.png");
-    let bg_grass_img_handle = asset_server.load("v2-images/bg-grass.jpg");
-    let rocks_and_spikes_top_img_handle = asset_server.load("v2-images/rocks-and-spikes.png");
-    let trees_left_img_handle = asset_server.load("v2-images/trees-left.png");
-    let trees_right_img_handle = asset_server.load("v2-images/trees-right.png");
-    let trees_bottom_img_handle = asset_server.load("v2-images/trees-bottom.png");
-
-    println!("creating background...");
-
-    // Spawn an entity with the background image as a sprite
-    commands.spawn(SpriteBundle {
-        texture: bg_grass_img_handle,
-        transform: Transform {
-            scale: Vec3::new(1.0, 1.0, 0.0),
-            translation: Vec3::new(0.0, 0.0, 1.0),
-            ..Default::default()
-        },
-        ..Default::default()
-    });
-
-    // Spawn rocks above walkable area
-    commands.spawn(SpriteBundle {
-        texture: rocks_and_spikes_top_img_handle,
-        transform: Transform {
-            scale: Vec3::new(1., 1., 0.0),
-            translation: Vec3::new(-105.,1305.0, 2.0),
+/// The time of day in the game world, which goes round much faster than real time.
+#[derive(Resource, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct WorldClock {
+    /// From `0.0` (midnight) up to `24.0`.
+    pub hour: f32,
+}
+
+impl Default for WorldClock {
+    fn default() -> Self {
+        Self { hour: START_HOUR }
+    }
+}
+
+impl WorldClock {
+    /// Move the clock forward, wrapping around at midnight.
+    pub fn advance(&mut self, hours: f32) {
+        self.hour = (self.hour + hours).rem_euclid(24.0);
+    }
+
+    /// The color to multiply the world with at this time of day.
+    pub fn tint(&self) -> Color {
+        let next = TINT_KEYFRAMES
+            .iter()
+            .position(|(hour, _)| *hour > self.hour)
+            .unwrap_or(TINT_KEYFRAMES.len() - 1)
+            .max(1);
+        let (from_hour, from) = TINT_KEYFRAMES[next - 1];
+        let (to_hour, to) = TINT_KEYFRAMES[next];
+        let t = ((self.hour - from_hour) / (to_hour - from_hour)).clamp(0.0, 1.0);
+        from.to_linear().mix(&to.to_linear(), t).into()
+    }
+}
+
+/// Scrolls with the camera at `factor` times its speed. `origin` is where the
+/// decoration is when the camera is at the world's origin.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Parallax {
+    pub factor: Vec2,
+    pub origin: Vec2,
+}
+
+/// Floats across the level, in world units per second.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Drift(pub Vec2);
+
+/// Loops through the frames of the decoration's sprite sheet.
+#[derive(Component, Debug)]
+pub struct DecorationAnimation {
+    timer: Timer,
+    frame: usize,
+    frames: usize,
+}
+
+/// Leans back and forth around the bottom of the image.
+#[derive(Component, Debug)]
+pub struct Sway {
+    /// The furthest it leans either way, in radians.
+    max_angle: f32,
+    /// How long swaying there and back takes, in seconds.
+    period: f32,
+    /// Where in the swing it started, from `0.0` to `1.0`.
+    phase: f32,
+}
+
+/// Tinted by the [`WorldClock`].
+#[derive(Component, Debug)]
+pub struct DayNightTint;
+
+/// Spawn one of the level's decorations (the grass, the trees around the
+/// edges, ...) and return its entity. Images packed into an atlas are found
+/// through the `manifest`.
+pub fn spawn_decoration(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    manifest: &AssetManifest,
+    texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+    decoration: &LevelDecoration,
+) -> Entity {
+    let packed = manifest.image_from_source(&decoration.image);
+    let texture = packed.map_or_else(
+        || asset_server.load(decoration.image.clone()),
+        |image| image.load(asset_server),
+    );
+    // Sprite sheets are cut out of the atlas by their layout instead.
+    let rect = packed
+        .and_then(|image| image.rect)
+        .filter(|_| decoration.animation.is_none())
+        .map(|rect| rect.as_rect());
+
+    let mut entity = commands.spawn((
+        Name::new(decoration.name.clone()),
+        SpriteBundle {
+            sprite: Sprite { rect, ..default() },
+            texture,
+            transform: Transform {
+                scale: Vec3::new(1., 1., 0.0),
+                translation: decoration.position.extend(decoration.layer),
+                ..Default::default()
+            },
             ..Default::default()
         },
-        ..Default::default()
-    });
-
-    // Spawn trees left
-    commands.spawn(SpriteBundle {
-        texture: trees_left_img_handle,
-        transform: Transform {
-            scale: Vec3::new(1., 1., 0.0),
-            translation: Vec3::new(MIN_X_POS - 660.,-180.0, 100.0),
-            ..Default::default()
+        Parallax {
+            factor: decoration.parallax,
+            origin: decoration.position,
         },
-        ..Default::default()
-    });
-
-    // Spawn trees right
-    commands.spawn(SpriteBundle {
-        texture: trees_right_img_handle,
-        transform: Transform {
-            scale: Vec3::new(1., 1., 0.0),
-            translation: Vec3::new(MAX_X_POS + 380.,-150.0, 100.0),
-            ..Default::default()
+        DayNightTint,
+    ));
+
+    if decoration.drift != Vec2::ZERO {
+        entity.insert(Drift(decoration.drift));
+    }
+
+    if let Some(animation) = decoration.animation {
+        let offset = packed.map_or(UVec2::ZERO, |image| image.offset());
+        entity.insert(animation_bundle(texture_atlas_layouts, animation, offset));
+    }
+
+    if let Some(sway) = decoration.sway {
+        entity.insert(sway_component(sway));
+    }
+
+    entity.id()
+}
+
+/// `offset` is where the sprite sheet starts in its image.
+fn animation_bundle(
+    texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+    animation: DecorationSpriteSheet,
+    offset: UVec2,
+) -> (TextureAtlas, DecorationAnimation) {
+    let columns = animation.columns.max(1);
+    let rows = animation.rows.max(1);
+    let frames = (columns * rows) as usize;
+    let layout =
+        TextureAtlasLayout::from_grid(animation.frame_size, columns, rows, None, Some(offset));
+
+    // Start somewhere random so that the same decoration placed twice
+    // doesn't move in lockstep.
+    let frame = rand::thread_rng().gen_range(0..frames);
+    let frame_duration = Duration::from_secs_f32(1.0 / animation.frames_per_second.max(0.1));
+
+    (
+        TextureAtlas {
+            layout: texture_atlas_layouts.add(layout),
+            index: frame,
         },
-        ..Default::default()
-    });
-
-    // Spawn trees bottom in front of EVERYTHING (TODO - refactor a more organized layering system based on y position)
-    commands.spawn(SpriteBundle {
-        texture: trees_bottom_img_handle,
-        transform: Transform {
-            scale: Vec3::new(1., 1., 0.0),
-            translation: Vec3::new(MIN_X_POS + 350.,MIN_Y_POS - 400., 200.0),
-            ..Default::default()
+        DecorationAnimation {
+            timer: Timer::new(frame_duration, TimerMode::Repeating),
+            frame,
+            frames,
         },
-        ..Default::default()
-    });
-
-    // commands.spawn(SpriteBundle {
-    //     texture: bg_texture_handle,
-    //     transform: Transform {
-    //         scale: Vec3::new(1.0, 1.0, 0.0),
-    //         translation: Vec3::new(0.0, 0.0, 100.0),
-    //         ..Default::default()
-    //     },
-    //     ..Default::default()
-    // });
+    )
+}
+
+fn sway_component(sway: DecorationSway) -> Sway {
+    Sway {
+        max_angle: sway.degrees.to_radians(),
+        period: sway.seconds.max(0.1),
+        // Like sprite sheets, start somewhere random so neighbours don't move
+        // in lockstep.
+        phase: rand::thread_rng().gen(),
+    }
+}
+
+fn tick_world_clock(time: Res<Time>, mut clock: ResMut<WorldClock>) {
+    clock.advance(24.0 * time.delta_seconds() / DAY_LENGTH.as_secs_f32());
+}
+
+fn tick_decoration_animations(time: Res<Time>, mut animations: Query<&mut DecorationAnimation>) {
+    for mut animation in &mut animations {
+        animation.timer.tick(time.delta());
+        let advanced = animation.timer.times_finished_this_tick() as usize;
+        animation.frame = (animation.frame + advanced) % animation.frames;
+    }
+}
+
+fn animate_decorations(mut animations: Query<(&DecorationAnimation, &mut TextureAtlas)>) {
+    for (animation, mut atlas) in &mut animations {
+        if atlas.index != animation.frame {
+            atlas.index = animation.frame;
+        }
+    }
+}
+
+fn drift_decorations(
+    time: Res<Time>,
+    world_bounds: Res<WorldBounds>,
+    mut decorations: Query<(&Drift, &mut Parallax)>,
+) {
+    let area = world_bounds.0.inflate(DRIFT_MARGIN);
+    for (drift, mut parallax) in &mut decorations {
+        let mut origin = parallax.origin + drift.0 * time.delta_seconds();
+        if origin.x > area.max.x {
+            origin.x = area.min.x;
+        } else if origin.x < area.min.x {
+            origin.x = area.max.x;
+        }
+        if origin.y > area.max.y {
+            origin.y = area.min.y;
+        } else if origin.y < area.min.y {
+            origin.y = area.max.y;
+        }
+        parallax.origin = origin;
+    }
+}
+
+fn apply_parallax(
+    cameras: Query<&Transform, With<CameraController>>,
+    mut decorations: Query<(&Parallax, &mut Transform), Without<CameraController>>,
+) {
+    let Ok(camera_transform) = cameras.get_single() else {
+        return;
+    };
+    let camera_position = camera_transform.translation.truncate();
+
+    for (parallax, mut transform) in &mut decorations {
+        let position = parallax.origin + camera_position * (Vec2::ONE - parallax.factor);
+        if transform.translation.truncate() != position {
+            transform.translation = position.extend(transform.translation.z);
+        }
+    }
+}
+
+/// Runs after [`apply_parallax`], which puts the decoration back where it
+/// belongs every frame, so the lean is never added up.
+fn sway_decorations(
+    time: Res<Time>,
+    images: Res<Assets<Image>>,
+    mut decorations: Query<(&Sway, &Sprite, &Handle<Image>, &mut Transform)>,
+) {
+    for (sway, sprite, image, mut transform) in &mut decorations {
+        let height = match sprite.rect {
+            Some(rect) => rect.height(),
+            None => match images.get(image) {
+                Some(image) => image.height() as f32,
+                None => continue,
+            },
+        };
+
+        let swing = time.elapsed_seconds() / sway.period + sway.phase;
+        let rotation = Quat::from_rotation_z(sway.max_angle * (swing * TAU).sin());
+        // Turn around the bottom middle of the image instead of its center.
+        let pivot = Vec3::new(0.0, -height / 2.0, 0.0);
+        transform.rotation = rotation;
+        transform.translation += pivot - rotation * pivot;
+    }
+}
+
+fn apply_day_night_tint(
+    clock: Res<WorldClock>,
+    mut sprites: Query<&mut Sprite, With<DayNightTint>>,
+) {
+    let tint = clock.tint();
+    for mut sprite in &mut sprites {
+        let color = tint.with_alpha(sprite.color.alpha());
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
 }
@@ -0,0 +1,231 @@
+//! Soft collision between our duck and everyone else's.
+//!
+//! Overlapping ducks are pushed apart (only ours moves, the others are wherever
+//! the server says they are). Walking into a duck fast enough is a bump: it
+//! plays a sound, squashes both ducks and knocks ours back a little. If the
+//! server advertises [`ProtocolFeature::Bump`] we also report the bump, so it
+//! can decide who gets shoved and tell the bumped player with `you_were_bumped`.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashSet};
+use serde::Deserialize;
+
+use crate::{
+    asset_manifest::AssetManifest, asset_tracking::LoadResource, audio::AudioCommands,
+    music::DucksMusic, screens::Screen, AppSet,
+};
+
+use super::{
+    interest::OutOfView,
+    movement::{apply_movement, MovementController},
+    other_player::OtherPlayer,
+    outbound_queue::OutboundQueue,
+    player::{Player, DUCK_COLLIDER_RADIUS},
+    player_registry::PlayerId,
+    protocol::{ProtocolFeature, ServerProtocol},
+    rate_limit::OutboundRateLimiter,
+    websocket_connect::C2SActionTypes,
+};
+
+/// How hard overlapping ducks push apart. Higher is stiffer.
+const PUSH_STIFFNESS: f32 = 150.0;
+
+/// How fast we have to walk into a duck for it to count as a bump.
+const BUMP_MIN_SPEED: f32 = 100.0;
+/// How fast we bounce back off a duck we bumped.
+const BUMP_RECOIL_SPEED: f32 = 250.0;
+
+const SQUASH_DURATION: Duration = Duration::from_millis(250);
+/// How far a bumped duck squashes, as a fraction of its size.
+const SQUASH_AMOUNT: f32 = 0.2;
+
+pub(super) fn plugin(app: &mut App) {
+    app.load_resource::<BumpAssets>();
+    app.add_event::<YouWereBumpedBevyEvent>();
+
+    app.add_systems(
+        Update,
+        (detect_bumps, you_were_bumped_handler, animate_squash)
+            .chain()
+            .in_set(AppSet::Update)
+            .after(apply_movement)
+            .run_if(in_state(Screen::Gameplay).and_then(resource_exists::<BumpAssets>)),
+    );
+}
+
+#[derive(Resource, Asset, Reflect, Clone)]
+pub struct BumpAssets {
+    #[dependency]
+    bump: Handle<AudioSource>,
+}
+
+impl FromWorld for BumpAssets {
+    fn from_world(world: &mut World) -> Self {
+        let manifest = world.resource::<AssetManifest>();
+        let assets = world.resource::<AssetServer>();
+        Self {
+            bump: manifest.load_sound(assets, "boing"),
+        }
+    }
+}
+
+/// Sent when the server decides someone shoved our duck.
+#[derive(Event, Debug, Clone, Deserialize)]
+pub struct YouWereBumpedBevyEvent {
+    pub by_player_uuid: String,
+    pub knockback_x: f32,
+    pub knockback_y: f32,
+}
+
+/// Squashes a duck for a moment after a bump.
+#[derive(Component, Debug)]
+struct Squash {
+    timer: Timer,
+    /// The scale to go back to afterwards.
+    base_scale: Vec3,
+}
+
+#[derive(serde::Serialize)]
+struct BumpRequestData {
+    player_uuid: String,
+    strength: f32,
+}
+
+#[derive(serde::Serialize)]
+struct BumpRequest {
+    action_type: String,
+    data: BumpRequestData,
+}
+
+fn build_bump_request_msg(player_uuid: String, strength: f32) -> String {
+    let bump_request = BumpRequest {
+        action_type: "bump".to_string(),
+        data: BumpRequestData {
+            player_uuid,
+            strength,
+        },
+    };
+
+    serde_json::ser::to_string(&bump_request).unwrap_or_else(|e| {
+        warn!("Couldn't convert bump request struct to string: {e}");
+        "".to_string()
+    })
+}
+
+fn squash(commands: &mut Commands, entity: Entity, transform: &Transform, squash: Option<&Squash>) {
+    commands.entity(entity).insert(Squash {
+        timer: Timer::new(SQUASH_DURATION, TimerMode::Once),
+        // Don't squash an already squashed duck any further.
+        base_scale: squash.map_or(transform.scale, |squash| squash.base_scale),
+    });
+}
+
+fn play_bump_sound(commands: &mut Commands, bump_assets: &BumpAssets) {
+    commands
+        .play_sound_effect(AudioBundle {
+            source: bump_assets.bump.clone(),
+            settings: PlaybackSettings::DESPAWN.with_speed(1.6),
+        })
+        .insert(DucksMusic);
+}
+
+fn detect_bumps(
+    mut commands: Commands,
+    time: Res<Time>,
+    bump_assets: Res<BumpAssets>,
+    server_protocol: Res<ServerProtocol>,
+    mut rate_limiter: ResMut<OutboundRateLimiter>,
+    mut outbound_queues: Query<&mut OutboundQueue>,
+    mut players: Query<(Entity, &Transform, &mut MovementController, Option<&Squash>), With<Player>>,
+    other_players: Query<
+        (Entity, &Transform, &PlayerId, Option<&Squash>),
+        (With<OtherPlayer>, Without<Player>, Without<OutOfView>),
+    >,
+    mut touching: Local<HashSet<Entity>>,
+) {
+    let Ok((player, player_transform, mut controller, player_squash)) = players.get_single_mut()
+    else {
+        touching.clear();
+        return;
+    };
+    let position = player_transform.translation.truncate();
+    let velocity = controller.max_speed * controller.speed_multiplier * controller.intent
+        + controller.knockback;
+
+    let mut still_touching = HashSet::default();
+    for (other, other_transform, player_id, other_squash) in &other_players {
+        let offset = position - other_transform.translation.truncate();
+        let overlap = DUCK_COLLIDER_RADIUS * 2.0 - offset.length();
+        if overlap <= 0.0 {
+            continue;
+        }
+        still_touching.insert(other);
+
+        // Push apart like a spring, the knockback fading out does the damping.
+        let away = offset.normalize_or(Vec2::Y);
+        controller.knockback += away * overlap * PUSH_STIFFNESS * time.delta_seconds();
+
+        if touching.contains(&other) {
+            continue;
+        }
+        let approach_speed = velocity.dot(-away);
+        if approach_speed < BUMP_MIN_SPEED {
+            continue;
+        }
+
+        info!("Bumped into {} at {:.0}/s", player_id.0, approach_speed);
+        controller.knockback += away * BUMP_RECOIL_SPEED;
+        squash(&mut commands, player, player_transform, player_squash);
+        squash(&mut commands, other, other_transform, other_squash);
+        play_bump_sound(&mut commands, &bump_assets);
+
+        if server_protocol.has_feature(ProtocolFeature::Bump)
+            && rate_limiter.try_acquire(C2SActionTypes::Bump, time.elapsed())
+        {
+            for mut queue in &mut outbound_queues {
+                queue.push(
+                    C2SActionTypes::Bump,
+                    build_bump_request_msg(player_id.0.clone(), approach_speed),
+                );
+            }
+        }
+    }
+
+    *touching = still_touching;
+}
+
+fn you_were_bumped_handler(
+    mut commands: Commands,
+    mut event_reader: EventReader<YouWereBumpedBevyEvent>,
+    bump_assets: Res<BumpAssets>,
+    mut players: Query<(Entity, &Transform, &mut MovementController, Option<&Squash>), With<Player>>,
+) {
+    for e in event_reader.read() {
+        info!("Bumped by {}", e.by_player_uuid);
+        for (player, transform, mut controller, player_squash) in &mut players {
+            controller.knockback += Vec2::new(e.knockback_x, e.knockback_y);
+            squash(&mut commands, player, transform, player_squash);
+        }
+        play_bump_sound(&mut commands, &bump_assets);
+    }
+}
+
+fn animate_squash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut squashed: Query<(Entity, &mut Squash, &mut Transform)>,
+) {
+    for (entity, mut squash, mut transform) in &mut squashed {
+        squash.timer.tick(time.delta());
+        if squash.timer.finished() {
+            transform.scale = squash.base_scale;
+            commands.entity(entity).remove::<Squash>();
+            continue;
+        }
+
+        // Squash flat and wide, then spring back.
+        let amount = SQUASH_AMOUNT * (squash.timer.fraction() * std::f32::consts::PI).sin();
+        transform.scale = squash.base_scale * Vec3::new(1.0 + amount, 1.0 - amount, 1.0);
+    }
+}
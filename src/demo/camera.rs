@@ -0,0 +1,257 @@
+//! A camera controller that follows a target entity (our duck).
+//!
+//! The camera only starts moving once the target leaves a dead zone around the
+//! middle of the screen, looks ahead in the direction the target is moving and
+//! eases towards where it wants to be. It is kept inside the world bounds from
+//! [`movement`](super::movement) based on how much of the world fits on
+//! screen, shakes on [`CameraShake`] events and zooms between a few levels.
+
+use bevy::{prelude::*, render::camera::ScalingMode};
+use rand::prelude::*;
+
+use crate::AppSet;
+
+use super::{
+    movement::{apply_movement, WorldBounds},
+    player::Player,
+};
+
+/// However the window is shaped, at least this much of the world is visible
+/// (before zooming). Matches the original 600x800 window on a portrait phone.
+const MIN_VISIBLE_WORLD_WIDTH: f32 = 600.0;
+const MIN_VISIBLE_WORLD_HEIGHT: f32 = 600.0;
+
+/// The projection scales `Z` cycles through. Bigger shows more of the pond.
+const ZOOM_LEVELS: [f32; 3] = [0.75, 1.0, 1.5];
+const DEFAULT_ZOOM_LEVEL: usize = 1;
+
+/// How quickly zooming eases in, higher is snappier.
+const ZOOM_SMOOTHING: f32 = 8.0;
+/// How quickly the look-ahead follows changes in direction.
+const LOOK_AHEAD_SMOOTHING: f32 = 3.0;
+
+/// How far the camera is thrown around at full trauma, in world units.
+const MAX_SHAKE_OFFSET: f32 = 12.0;
+/// How much trauma wears off per second.
+const TRAUMA_DECAY: f32 = 1.5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<CameraController>();
+    app.add_event::<CameraShake>();
+
+    app.add_systems(Update, cycle_zoom_level.in_set(AppSet::RecordInput));
+    app.add_systems(Update, set_camera_scaling_mode);
+    app.add_systems(
+        Update,
+        (follow_new_player, add_camera_shake, update_camera)
+            .chain()
+            .in_set(AppSet::Update)
+            .after(apply_movement),
+    );
+}
+
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+pub struct CameraController {
+    /// The entity to follow, if any.
+    pub target: Option<Entity>,
+    /// How quickly the camera catches up with the target, higher is snappier.
+    pub smoothing: f32,
+    /// Half the size of the box around the camera focus the target can move
+    /// around in without moving the camera.
+    pub dead_zone: Vec2,
+    /// How far ahead of a moving target to look, in seconds of its velocity.
+    pub look_ahead: f32,
+    /// Index into [`ZOOM_LEVELS`].
+    pub zoom_level: usize,
+    /// Where the camera is, before shaking.
+    position: Vec2,
+    /// The point the dead zone is centered on.
+    focus: Vec2,
+    look_ahead_offset: Vec2,
+    last_target_position: Option<Vec2>,
+    /// Shake strength from `0.0` to `1.0`. The shake grows with its square.
+    trauma: f32,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            target: None,
+            smoothing: 6.0,
+            dead_zone: Vec2::new(40.0, 30.0),
+            look_ahead: 0.3,
+            zoom_level: DEFAULT_ZOOM_LEVEL,
+            position: Vec2::ZERO,
+            focus: Vec2::ZERO,
+            look_ahead_offset: Vec2::ZERO,
+            last_target_position: None,
+            trauma: 0.0,
+        }
+    }
+}
+
+impl CameraController {
+    /// Follow `target`, jumping straight to `position` instead of sliding over.
+    pub fn snap_to(&mut self, target: Entity, position: Vec2) {
+        self.target = Some(target);
+        self.position = position;
+        self.focus = position;
+        self.look_ahead_offset = Vec2::ZERO;
+        self.last_target_position = Some(position);
+    }
+
+    /// Jump to `position` without following anything.
+    pub fn look_at(&mut self, position: Vec2) {
+        self.target = None;
+        self.position = position;
+        self.focus = position;
+        self.look_ahead_offset = Vec2::ZERO;
+        self.last_target_position = None;
+    }
+}
+
+/// Shake the camera. Trauma from several events adds up, to at most `1.0`.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct CameraShake {
+    pub trauma: f32,
+}
+
+/// How much of the way to a goal to move this frame, so that easing is
+/// independent of the frame rate.
+fn blend(rate: f32, delta_seconds: f32) -> f32 {
+    1.0 - (-rate * delta_seconds).exp()
+}
+
+/// Keep a camera showing `view_size` of the world inside `bounds`, centering
+/// it on any axis where the view is bigger than the world.
+fn clamp_to_world(position: Vec2, view_size: Vec2, bounds: Rect) -> Vec2 {
+    let min = bounds.min + view_size / 2.0;
+    let max = bounds.max - view_size / 2.0;
+    let center = bounds.center();
+
+    Vec2::new(
+        if min.x <= max.x {
+            position.x.clamp(min.x, max.x)
+        } else {
+            center.x
+        },
+        if min.y <= max.y {
+            position.y.clamp(min.y, max.y)
+        } else {
+            center.y
+        },
+    )
+}
+
+/// Scale the view with the window instead of showing one world unit per pixel,
+/// so small screens don't see less of the pond and big ones don't see all of it.
+fn set_camera_scaling_mode(
+    mut cameras: Query<&mut OrthographicProjection, Added<CameraController>>,
+) {
+    for mut projection in &mut cameras {
+        projection.scaling_mode = ScalingMode::AutoMin {
+            min_width: MIN_VISIBLE_WORLD_WIDTH,
+            min_height: MIN_VISIBLE_WORLD_HEIGHT,
+        };
+    }
+}
+
+/// Center the camera on our duck as soon as it spawns.
+fn follow_new_player(
+    new_players: Query<(Entity, &Transform), Added<Player>>,
+    world_bounds: Res<WorldBounds>,
+    mut cameras: Query<(&mut CameraController, &mut Transform, &OrthographicProjection), Without<Player>>,
+) {
+    for (player, player_transform) in &new_players {
+        let position = player_transform.translation.truncate();
+        for (mut controller, mut transform, projection) in &mut cameras {
+            controller.snap_to(player, position);
+            let clamped = clamp_to_world(position, projection.area.size(), world_bounds.0);
+            transform.translation = clamped.extend(transform.translation.z);
+        }
+    }
+}
+
+fn add_camera_shake(
+    mut shake_events: EventReader<CameraShake>,
+    mut cameras: Query<&mut CameraController>,
+) {
+    for e in shake_events.read() {
+        for mut controller in &mut cameras {
+            controller.trauma = (controller.trauma + e.trauma).min(1.0);
+        }
+    }
+}
+
+fn cycle_zoom_level(input: Res<ButtonInput<KeyCode>>, mut cameras: Query<&mut CameraController>) {
+    if input.just_pressed(KeyCode::KeyZ) {
+        for mut controller in &mut cameras {
+            controller.zoom_level = (controller.zoom_level + 1) % ZOOM_LEVELS.len();
+        }
+    }
+}
+
+pub(super) fn update_camera(
+    time: Res<Time>,
+    world_bounds: Res<WorldBounds>,
+    mut cameras: Query<(&mut CameraController, &mut Transform, &mut OrthographicProjection)>,
+    targets: Query<&Transform, Without<CameraController>>,
+) {
+    let delta_seconds = time.delta_seconds();
+    if delta_seconds <= 0.0 {
+        return;
+    }
+
+    for (mut controller, mut transform, mut projection) in &mut cameras {
+        let zoom = ZOOM_LEVELS[controller.zoom_level.min(ZOOM_LEVELS.len() - 1)];
+        if projection.scale != zoom {
+            let scale = projection.scale;
+            projection.scale = if (zoom - scale).abs() < 0.001 {
+                zoom
+            } else {
+                scale + (zoom - scale) * blend(ZOOM_SMOOTHING, delta_seconds)
+            };
+        }
+
+        let target_position = controller
+            .target
+            .and_then(|target| targets.get(target).ok())
+            .map(|target_transform| target_transform.translation.truncate());
+
+        if let Some(target_position) = target_position {
+            let velocity = controller
+                .last_target_position
+                .map_or(Vec2::ZERO, |last| (target_position - last) / delta_seconds);
+            controller.last_target_position = Some(target_position);
+
+            // Drag the dead zone along once the target pushes against its edge.
+            let offset = target_position - controller.focus;
+            let dead_zone = controller.dead_zone;
+            controller.focus += offset - offset.clamp(-dead_zone, dead_zone);
+
+            let wanted_look_ahead = velocity * controller.look_ahead;
+            let look_ahead_offset = controller.look_ahead_offset;
+            controller.look_ahead_offset = look_ahead_offset
+                .lerp(wanted_look_ahead, blend(LOOK_AHEAD_SMOOTHING, delta_seconds));
+
+            let goal = controller.focus + controller.look_ahead_offset;
+            let smoothing = controller.smoothing;
+            let position = controller.position;
+            controller.position = position.lerp(goal, blend(smoothing, delta_seconds));
+        }
+
+        controller.position =
+            clamp_to_world(controller.position, projection.area.size(), world_bounds.0);
+
+        let mut shake = Vec2::ZERO;
+        if controller.trauma > 0.0 {
+            let rng = &mut rand::thread_rng();
+            let strength = MAX_SHAKE_OFFSET * controller.trauma * controller.trauma;
+            shake = Vec2::new(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0)) * strength;
+            controller.trauma = (controller.trauma - TRAUMA_DECAY * delta_seconds).max(0.0);
+        }
+
+        transform.translation = (controller.position + shake).extend(transform.translation.z);
+    }
+}
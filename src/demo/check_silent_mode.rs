@@ -1,51 +1,200 @@
-use std::sync::Arc;
+//! Notices when the browser won't let us play sound yet, and asks for a tap.
+//!
+//! Browsers keep a page's audio suspended until the player interacts with it,
+//! and can suspend it again later (e.g. a phone call on mobile).
+//! `web/restart-audio-context.js` keeps track of the page's `AudioContext`s and
+//! resumes them whenever the player interacts. Here we poll their state into
+//! [`SoundAvailability`], cover the screen with a "Tap to enable sound" overlay
+//! while they're suspended, and show a muted icon in gameplay while nothing
+//! can be heard.
 
-use futures::lock::Mutex;
+use bevy::{prelude::*, ui::FocusPolicy};
 
-#[cfg(target_arch = "wasm32")]
-pub mod wasm_silent_mode {
+use crate::{
+    audio::{AudioBus, AudioSettings},
+    screens::Screen,
+    theme::prelude::*,
+};
 
-    use wasm_bindgen::prelude::*;
-    use wasm_bindgen::JsValue;
+use super::hud_layout::ResponsiveHud;
+
+/// How often we ask the browser whether audio is running.
+const POLL_SECS: f32 = 0.5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SoundAvailability>();
+    app.insert_resource(SoundAvailabilityPoll(Timer::from_seconds(
+        POLL_SECS,
+        TimerMode::Repeating,
+    )));
+
+    app.add_systems(OnEnter(Screen::Gameplay), spawn_muted_icon);
+    app.add_systems(
+        Update,
+        (
+            poll_sound_availability,
+            (show_sound_prompt, update_muted_icon),
+        )
+            .chain(),
+    );
+}
+
+/// Whether the browser lets us play sound.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SoundAvailability {
+    /// We haven't been able to tell yet.
+    #[default]
+    Unknown,
+    Available,
+    /// Audio is suspended until the player interacts with the page.
+    Blocked,
+}
 
-    #[wasm_bindgen(inline_js = r#"
-    export function isSilentMode() {
-        return new Promise((resolve) => {
-            const audio = new Audio();
-            audio.src = 'data:audio/mp3;base64,...'; // A silent MP3 encoded in base64
-            audio.play().then(() => resolve(false)).catch(() => resolve(true));
-        });
+impl SoundAvailability {
+    pub fn is_blocked(self) -> bool {
+        self == SoundAvailability::Blocked
     }
-    "#)]
+}
+
+#[derive(Resource)]
+struct SoundAvailabilityPoll(Timer);
+
+/// Covers the screen while audio is blocked. Tapping it enables sound.
+#[derive(Component)]
+struct SoundPrompt;
+
+/// Shown in gameplay while nothing can be heard.
+#[derive(Component)]
+struct MutedIcon;
+
+#[cfg(target_arch = "wasm32")]
+mod web_audio {
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen(module = "/web/audio-contexts.js")]
     extern "C" {
-        #[wasm_bindgen(js_name = "isSilentMode")]
-        pub async fn is_silent_mode_js() -> JsValue;
+        #[wasm_bindgen(js_name = "audioContextsBlocked")]
+        fn audio_contexts_blocked() -> JsValue;
+
+        #[wasm_bindgen(js_name = "resumeAudioContexts")]
+        pub fn resume_audio_contexts();
     }
 
+    /// `None` if there's no audio context to ask about yet.
+    pub fn is_blocked() -> Option<bool> {
+        audio_contexts_blocked().as_bool()
+    }
 }
 
-pub async fn check_silent_mode(shared_status: Arc<Mutex<Option<bool>>>) {
-    
-    let mut status = shared_status.lock().await;
-
-    #[cfg(not(target_arch = "wasm32"))] {   
-        println!("Not wasm, not checking silent mode...");  
-        *status = Some(false);
+fn poll_sound_availability(
+    time: Res<Time>,
+    mut poll: ResMut<SoundAvailabilityPoll>,
+    mut availability: ResMut<SoundAvailability>,
+) {
+    if !poll.0.tick(time.delta()).just_finished() {
+        return;
     }
-    
-    #[cfg(target_arch = "wasm32")] {
 
-        use wasm_silent_mode::is_silent_mode_js;
+    #[cfg(target_arch = "wasm32")]
+    let new_availability = match web_audio::is_blocked() {
+        Some(true) => SoundAvailability::Blocked,
+        Some(false) => SoundAvailability::Available,
+        None => SoundAvailability::Unknown,
+    };
+    // Native audio never waits for permission.
+    #[cfg(not(target_arch = "wasm32"))]
+    let new_availability = SoundAvailability::Available;
+
+    if availability.set_if_neq(new_availability) {
+        info!("Sound is now {:?}", new_availability);
+    }
+}
 
-        let result = is_silent_mode_js().await;
-        let is_silent = result.as_bool().unwrap_or(false);
+fn show_sound_prompt(
+    mut commands: Commands,
+    availability: Res<SoundAvailability>,
+    prompts: Query<Entity, With<SoundPrompt>>,
+) {
+    if !availability.is_changed() {
+        return;
+    }
 
-        if is_silent {
-            web_sys::console::log_1(&"Silencer is ON!".into());
-        } else {
-            web_sys::console::log_1(&"Silencer is OFF!".into());
+    if !availability.is_blocked() {
+        for prompt in &prompts {
+            commands.entity(prompt).despawn_recursive();
         }
-        *status = Some(is_silent);
+        return;
+    }
+    if !prompts.is_empty() {
+        return;
     }
 
+    commands
+        .ui_root()
+        .insert((
+            Name::new("Sound prompt"),
+            SoundPrompt,
+            Interaction::default(),
+            // Don't let the tap fall through to whatever is underneath.
+            FocusPolicy::Block,
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            ZIndex::Global(i32::MAX),
+        ))
+        .with_children(|children| {
+            children.header("Tap to enable sound");
+            children.label("Your browser keeps the game quiet until you do");
+        })
+        .observe(enable_sound);
+}
+
+/// The tap itself already resumes audio (see `web/restart-audio-context.js`);
+/// this is a second try for browsers that let us resume outside of it.
+fn enable_sound(_trigger: Trigger<OnPress>) {
+    #[cfg(target_arch = "wasm32")]
+    web_audio::resume_audio_contexts();
+}
+
+fn spawn_muted_icon(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        Name::new("Muted icon"),
+        MutedIcon,
+        TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            text: Text::from_section(
+                "Sound off",
+                TextStyle {
+                    font: asset_server.load("FiraSans-Bold.ttf"),
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                },
+            ),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        // Under the score and position.
+        ResponsiveHud::top_left(
+            (Val::Percent(3.), Val::Percent(9.)),
+            (Val::Percent(2.), Val::Px(68.)),
+        ),
+        StateScoped(Screen::Gameplay),
+    ));
+}
+
+fn update_muted_icon(
+    availability: Res<SoundAvailability>,
+    settings: Res<AudioSettings>,
+    mut icons: Query<&mut Visibility, With<MutedIcon>>,
+) {
+    let muted = availability.is_blocked() || settings.gain(AudioBus::Master) == 0.0;
+    let new_visibility = if muted {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut visibility in &mut icons {
+        visibility.set_if_neq(new_visibility);
+    }
 }
@@ -0,0 +1,225 @@
+//! Lightweight 2D collision against static obstacles like trees and rocks.
+//!
+//! Obstacles are entities with a [`Collider`] and an [`Obstacle`] marker,
+//! spawned from the level's colliders. They are bucketed into an
+//! [`ObstacleGrid`] so a moving duck only checks the few obstacles near it.
+//! Overlaps are resolved by pushing the duck straight out of the obstacle,
+//! which keeps the part of its movement along the obstacle's edge, so ducks
+//! slide along walls instead of sticking to them.
+
+use bevy::{prelude::*, utils::HashMap};
+
+/// The side length of the grid cells obstacles are bucketed into. About the
+/// size of a duck, so most lookups only touch a few cells.
+const GRID_CELL_SIZE: f32 = 128.0;
+
+/// How many times to push out of overlapping obstacles per move. More than
+/// one so that corners where two obstacles meet resolve cleanly.
+const RESOLVE_ITERATIONS: usize = 3;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ObstacleGrid>();
+    app.add_systems(PreUpdate, rebuild_obstacle_grid);
+}
+
+/// A collision shape centered on the entity's translation.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub enum Collider {
+    Aabb { half_size: Vec2 },
+    Circle { radius: f32 },
+}
+
+impl Collider {
+    fn bounding_half_size(&self) -> Vec2 {
+        match self {
+            Collider::Aabb { half_size } => *half_size,
+            Collider::Circle { radius } => Vec2::splat(*radius),
+        }
+    }
+}
+
+/// Marks a [`Collider`] that never moves and blocks ducks.
+#[derive(Component, Debug)]
+pub struct Obstacle;
+
+/// Obstacles bucketed by the grid cells they overlap.
+#[derive(Resource, Debug, Default)]
+pub struct ObstacleGrid {
+    cells: HashMap<IVec2, Vec<(Entity, Vec2, Collider)>>,
+}
+
+impl ObstacleGrid {
+    fn cells_overlapping(center: Vec2, half_size: Vec2) -> impl Iterator<Item = IVec2> {
+        let min = ((center - half_size) / GRID_CELL_SIZE).floor().as_ivec2();
+        let max = ((center + half_size) / GRID_CELL_SIZE).floor().as_ivec2();
+        (min.x..=max.x).flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+    }
+
+    fn insert(&mut self, entity: Entity, position: Vec2, collider: Collider) {
+        for cell in Self::cells_overlapping(position, collider.bounding_half_size()) {
+            self.cells
+                .entry(cell)
+                .or_default()
+                .push((entity, position, collider));
+        }
+    }
+
+    /// Every obstacle that might overlap the given area, each once.
+    fn nearby(&self, center: Vec2, half_size: Vec2) -> Vec<(Vec2, Collider)> {
+        let mut seen: Vec<Entity> = vec![];
+        let mut found = vec![];
+        for cell in Self::cells_overlapping(center, half_size) {
+            for (entity, position, collider) in self.cells.get(&cell).into_iter().flatten() {
+                if !seen.contains(entity) {
+                    seen.push(*entity);
+                    found.push((*position, *collider));
+                }
+            }
+        }
+        found
+    }
+
+    /// Move a `collider` at `position` out of any obstacles it overlaps.
+    pub fn resolve(&self, position: Vec2, collider: Collider) -> Vec2 {
+        let mut position = position;
+        let nearby = self.nearby(position, collider.bounding_half_size());
+
+        for _ in 0..RESOLVE_ITERATIONS {
+            let mut moved = false;
+            for (obstacle_position, obstacle) in &nearby {
+                if let Some(push) = penetration(position, collider, *obstacle_position, *obstacle) {
+                    position += push;
+                    moved = true;
+                }
+            }
+            if !moved {
+                break;
+            }
+        }
+
+        position
+    }
+}
+
+/// How far to move `a` so it no longer overlaps `b`, if it does.
+fn penetration(a_position: Vec2, a: Collider, b_position: Vec2, b: Collider) -> Option<Vec2> {
+    match (a, b) {
+        (Collider::Circle { radius }, Collider::Circle { radius: other_radius }) => {
+            let offset = a_position - b_position;
+            let distance = offset.length();
+            let overlap = radius + other_radius - distance;
+            (overlap > 0.0).then(|| offset.normalize_or(Vec2::Y) * overlap)
+        }
+        (Collider::Circle { radius }, Collider::Aabb { half_size }) => {
+            circle_out_of_aabb(a_position, radius, b_position, half_size)
+        }
+        (Collider::Aabb { half_size }, Collider::Circle { radius }) => {
+            circle_out_of_aabb(b_position, radius, a_position, half_size).map(|push| -push)
+        }
+        (Collider::Aabb { half_size }, Collider::Aabb { half_size: other_half_size }) => {
+            let offset = a_position - b_position;
+            let overlap = half_size + other_half_size - offset.abs();
+            if overlap.x <= 0.0 || overlap.y <= 0.0 {
+                return None;
+            }
+            // Push out along the axis with the least overlap.
+            Some(if overlap.x < overlap.y {
+                Vec2::new(overlap.x * offset.x.signum(), 0.0)
+            } else {
+                Vec2::new(0.0, overlap.y * offset.y.signum())
+            })
+        }
+    }
+}
+
+/// How far to move a circle so it no longer overlaps a box, if it does.
+fn circle_out_of_aabb(
+    circle_position: Vec2,
+    radius: f32,
+    box_position: Vec2,
+    half_size: Vec2,
+) -> Option<Vec2> {
+    let offset = circle_position - box_position;
+    let closest = offset.clamp(-half_size, half_size);
+
+    if closest != offset {
+        // The center is outside the box, push away from the closest point.
+        let from_closest = offset - closest;
+        let distance = from_closest.length();
+        return (distance < radius).then(|| from_closest / distance * (radius - distance));
+    }
+
+    // The center is inside the box, push out through the nearest side.
+    let to_edge = half_size - offset.abs();
+    Some(if to_edge.x < to_edge.y {
+        Vec2::new((to_edge.x + radius) * offset.x.signum(), 0.0)
+    } else {
+        Vec2::new(0.0, (to_edge.y + radius) * offset.y.signum())
+    })
+}
+
+/// Obstacles don't move, so the grid only changes when obstacles come or go.
+fn rebuild_obstacle_grid(
+    mut grid: ResMut<ObstacleGrid>,
+    obstacles: Query<(Entity, &Transform, &Collider), With<Obstacle>>,
+    added: Query<(), Added<Obstacle>>,
+    mut removed: RemovedComponents<Obstacle>,
+) {
+    let removed_any = removed.read().count() > 0;
+    if added.is_empty() && !removed_any {
+        return;
+    }
+
+    grid.cells.clear();
+    for (entity, transform, collider) in &obstacles {
+        grid.insert(entity, transform.translation.truncate(), *collider);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUCK: Collider = Collider::Circle { radius: 10.0 };
+    const WALL: Collider = Collider::Aabb {
+        half_size: Vec2::splat(50.0),
+    };
+
+    fn grid_with(obstacles: &[(Vec2, Collider)]) -> ObstacleGrid {
+        let mut grid = ObstacleGrid::default();
+        for (index, (position, collider)) in obstacles.iter().enumerate() {
+            grid.insert(Entity::from_raw(index as u32), *position, *collider);
+        }
+        grid
+    }
+
+    #[test]
+    fn circle_is_pushed_out_of_a_box() {
+        let push = penetration(Vec2::new(0.0, 55.0), DUCK, Vec2::ZERO, WALL);
+        assert_eq!(push, Some(Vec2::new(0.0, 5.0)));
+
+        let push = penetration(Vec2::new(0.0, 40.0), DUCK, Vec2::ZERO, WALL);
+        assert_eq!(push, Some(Vec2::new(0.0, 20.0)));
+    }
+
+    #[test]
+    fn shapes_that_only_touch_do_not_overlap() {
+        assert_eq!(penetration(Vec2::new(0.0, 60.0), DUCK, Vec2::ZERO, WALL), None);
+        assert_eq!(penetration(Vec2::new(20.0, 0.0), DUCK, Vec2::ZERO, DUCK), None);
+    }
+
+    #[test]
+    fn moving_into_a_box_edge_slides_along_it() {
+        let grid = grid_with(&[(Vec2::ZERO, WALL)]);
+        let resolved = grid.resolve(Vec2::new(20.0, 55.0), DUCK);
+        assert_eq!(resolved, Vec2::new(20.0, 60.0));
+    }
+
+    #[test]
+    fn corner_between_two_boxes_resolves_out_of_both() {
+        let grid = grid_with(&[(Vec2::ZERO, WALL), (Vec2::new(100.0, 100.0), WALL)]);
+        let resolved = grid.resolve(Vec2::new(55.0, 55.0), DUCK);
+        assert!(penetration(resolved, DUCK, Vec2::ZERO, WALL).is_none());
+        assert!(penetration(resolved, DUCK, Vec2::new(100.0, 100.0), WALL).is_none());
+    }
+}
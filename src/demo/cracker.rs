@@ -1,53 +1,79 @@
 use bevy::{prelude::*, utils::info};
 
-use super::websocket_connect::MoveCrackersBevyEvent;
+use crate::{asset_manifest::AssetManifest, asset_tracking::LoadResource, audio::AudioCommands};
+
+use super::{camera::CameraShake, websocket_connect::MoveCrackersBevyEvent};
 
 #[derive(Component)]
-struct _CrackerComponent;
+pub struct Cracker;
 
 #[derive(Component)]
 struct _CrackerText;
 
 const CRACKER_TEXT_OFFSET: f32 = 22.;
 
-#[derive(Resource)]
-pub struct YouGotCrackerSoundFx {
-    pub sound_handle: Handle<AudioSource>,
+/// How hard the camera shakes when you grab the crackers.
+const CRACKER_PICKUP_TRAUMA: f32 = 0.6;
+
+#[derive(Resource, Asset, Reflect, Clone)]
+pub struct CrackerAssets {
+    #[dependency]
+    image: Handle<Image>,
+    /// Where the crackers are in `image`, if they were packed into an atlas.
+    image_rect: Option<URect>,
+    /// For the points on the crackers.
+    #[dependency]
+    font: Handle<Font>,
+    /// Played when we're the one who got the crackers.
+    #[dependency]
+    pub you_got_crackers: Handle<AudioSource>,
+    /// Played whenever the crackers move.
+    #[dependency]
+    pub chewing: Handle<AudioSource>,
 }
-#[derive(Resource)]
-pub struct MoveCrackerSoundFx {
-    pub sound_handle: Handle<AudioSource>,
+
+impl FromWorld for CrackerAssets {
+    fn from_world(world: &mut World) -> Self {
+        let manifest = world.resource::<AssetManifest>();
+        let assets = world.resource::<AssetServer>();
+        let image = manifest.image("cracker");
+        Self {
+            image: image.load(assets),
+            image_rect: image.rect,
+            font: manifest.load_font(assets, "fira_sans_bold"),
+            you_got_crackers: manifest.load_sound(assets, "boing"),
+            chewing: manifest.load_sound(assets, "chewing"),
+        }
+    }
 }
 
 pub(super) fn plugin(_app: &mut App) {
     // WIP - cracker stuff
-    _app.add_systems(Startup, _create_cracker);
-    _app.add_systems(Startup, _create_cracker_text);
-    _app.add_systems(Startup, cracker_sounds_setup);
+    _app.load_resource::<CrackerAssets>();
+    _app.add_systems(
+        Update,
+        (_create_cracker, _create_cracker_text).run_if(resource_added::<CrackerAssets>),
+    );
     _app.add_systems(Update, listen_for_move_cracker_bevy_event);
 }
 
-fn cracker_sounds_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let you_got_cracker_sound_handle = asset_server.load("audio/sound_effects/boing.wav");
-    let cracker_move_sound_handle = asset_server.load("audio/sound_effects/chewing-cracker-sound.wav");
-
-    // Store the handle in a resource
-    commands.insert_resource(YouGotCrackerSoundFx { sound_handle: you_got_cracker_sound_handle });
-    commands.insert_resource(MoveCrackerSoundFx { sound_handle: cracker_move_sound_handle });
-}
-
 fn listen_for_move_cracker_bevy_event(
     mut commands: Commands,
     mut bevy_move_crackers_event_reader: EventReader<MoveCrackersBevyEvent>,
     mut param_set: ParamSet<(
-        Query<&mut Transform, With<_CrackerComponent>>,
+        Query<&mut Transform, With<Cracker>>,
         Query<&mut Transform, With<_CrackerText>>,
         Query<&mut Text, With<_CrackerText>>,
     )>,
-    audio: Res<MoveCrackerSoundFx>,
-    audio_assets: Res<Assets<AudioSource>>,
+    cracker_assets: Option<Res<CrackerAssets>>,
+    mut camera_shake_event_writer: EventWriter<CameraShake>,
 ) {
     for e in bevy_move_crackers_event_reader.read() {
+        if e.you_got_crackers {
+            camera_shake_event_writer.send(CameraShake {
+                trauma: CRACKER_PICKUP_TRAUMA,
+            });
+        }
 
         info!(
             "Moving crackers! x: {:?}, y: {:?}",
@@ -70,28 +96,28 @@ fn listen_for_move_cracker_bevy_event(
             transform.sections[0].value = e.points.to_string();
         }
 
-        // Play chewing sound effect
-        if let Some(_) = audio_assets.get(&audio.sound_handle) {
-            // Spawn an audio source to play the sound
-            commands.spawn(AudioSourceBundle {
-                source: audio.sound_handle.clone(), // Clone the handle to use it
-                ..Default::default()                // Use default values for other fields
+        // Play chewing sound effect. It's loaded on the loading screen, before
+        // the server could have told us about any crackers.
+        if let Some(cracker_assets) = cracker_assets.as_ref() {
+            commands.play_sound_effect(AudioBundle {
+                source: cracker_assets.chewing.clone(),
+                settings: PlaybackSettings::DESPAWN,
             });
             println!("Playing cracker moved sound.");
-        } else {
-            println!("Audio not loaded yet.");
         }
     }
 }
 
-fn _create_cracker(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let texture_handle = asset_server.load("images/cracker-v1.png");
-
+fn _create_cracker(mut commands: Commands, cracker_assets: Res<CrackerAssets>) {
     println!("creating cracker...");
 
     commands
         .spawn(SpriteBundle {
-            texture: texture_handle,
+            texture: cracker_assets.image.clone(),
+            sprite: Sprite {
+                rect: cracker_assets.image_rect.map(|rect| rect.as_rect()),
+                ..default()
+            },
             transform: Transform {
                 scale: Vec3::new(0.075, 0.075, 0.0),
                 translation: Vec3::new(0.0, 0.0, 5.0),
@@ -99,10 +125,10 @@ fn _create_cracker(mut commands: Commands, asset_server: Res<AssetServer>) {
             },
             ..Default::default()
         })
-        .insert(_CrackerComponent);
+        .insert(Cracker);
 }
 
-fn _create_cracker_text(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn _create_cracker_text(mut commands: Commands, cracker_assets: Res<CrackerAssets>) {
     println!("inserting text!!");
 
     commands
@@ -110,7 +136,7 @@ fn _create_cracker_text(mut commands: Commands, asset_server: Res<AssetServer>)
             text: Text::from_section(
                 "10".to_string(), // The text to display
                 TextStyle {
-                    font: asset_server.load("FiraSans-Bold.ttf"),
+                    font: cracker_assets.font.clone(),
                     font_size: 16.0,
                     color: Color::WHITE,
                 },
@@ -0,0 +1,206 @@
+//! Duck animation, for our duck and everyone else's.
+//!
+//! The clips live in `assets/animations/duck.anim.json` and are played by the
+//! generic [`animation`](super::animation) system. Our duck walks while it's
+//! being moved; other ducks walk a cycle whenever the server says they moved.
+//! Either way, the walk cycle's `footstep` events play step sounds.
+//!
+//! Our duck and everyone else's share one [`DuckAssets`] collection.
+//!
+//! Other ducks' steps come from where they are, heard through the listener on
+//! our duck. A crowded pond would still be a wall of footsteps, so only the
+//! closest [`MAX_REMOTE_FOOTSTEPS`] play at once, and ducks further than
+//! [`FOOTSTEP_HEARING_DISTANCE`] away aren't heard at all.
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    asset_manifest::AssetManifest,
+    asset_tracking::LoadResource,
+    audio::{AudioCommands, AUDIO_SCALE},
+    AppSet,
+};
+
+use super::{
+    animation::{AnimationEvent, AnimationLibrary, SpriteAnimator},
+    movement::MovementController,
+    other_player::OtherPlayer,
+    player::Player,
+};
+
+pub const DUCK_ANIMATIONS_PATH: &str = "animations/duck.anim.json";
+/// The duck's sprite sheet in the [`AssetManifest`](crate::asset_manifest::AssetManifest).
+const DUCK_IMAGE: &str = "duck";
+/// The step sounds in the [`AssetManifest`](crate::asset_manifest::AssetManifest), one picked at random per step.
+const STEP_SOUNDS: [&str; 4] = ["step1", "step2", "step3", "step4"];
+
+pub const IDLE: &str = "idle";
+pub const WALK: &str = "walk";
+const FOOTSTEP: &str = "footstep";
+
+/// How many of other ducks' steps can play at the same time.
+const MAX_REMOTE_FOOTSTEPS: usize = 6;
+/// How far away, in pixels, other ducks' steps can still be heard. They'd be
+/// at 1/36th volume by now anyway (see [`AUDIO_SCALE`]).
+const FOOTSTEP_HEARING_DISTANCE: f32 = 6.0 / AUDIO_SCALE;
+
+pub(super) fn plugin(app: &mut App) {
+    app.load_resource::<DuckAssets>();
+
+    app.add_systems(
+        Update,
+        (
+            update_player_animation,
+            (play_player_footsteps, play_other_player_footsteps)
+                .run_if(resource_exists::<DuckAssets>),
+        )
+            .chain()
+            .in_set(AppSet::Update),
+    );
+}
+
+/// What every duck looks and sounds like.
+#[derive(Resource, Asset, Reflect, Clone)]
+pub struct DuckAssets {
+    #[dependency]
+    pub ducky: Handle<Image>,
+    /// Where the duck's sprite sheet starts in `ducky`, if it was packed into an atlas.
+    pub ducky_offset: UVec2,
+    #[dependency]
+    pub steps: Vec<Handle<AudioSource>>,
+    #[dependency]
+    pub animations: Handle<AnimationLibrary>,
+}
+
+impl FromWorld for DuckAssets {
+    fn from_world(world: &mut World) -> Self {
+        let manifest = world.resource::<AssetManifest>();
+        let assets = world.resource::<AssetServer>();
+        let ducky = manifest.image(DUCK_IMAGE);
+        Self {
+            ducky: ducky.load(assets),
+            ducky_offset: ducky.offset(),
+            steps: STEP_SOUNDS
+                .iter()
+                .map(|name| manifest.load_sound(assets, name))
+                .collect(),
+            animations: assets.load(DUCK_ANIMATIONS_PATH),
+        }
+    }
+}
+
+/// Face the way our duck is going, and walk while it's moving.
+fn update_player_animation(
+    mut player_query: Query<(&MovementController, &mut Sprite, &mut SpriteAnimator), With<Player>>,
+) {
+    for (controller, mut sprite, mut animator) in &mut player_query {
+        let dx = controller.intent.x;
+        if dx != 0.0 {
+            sprite.flip_x = dx < 0.0;
+        }
+
+        animator.play(if controller.intent == Vec2::ZERO {
+            IDLE
+        } else {
+            WALK
+        });
+    }
+}
+
+fn play_player_footsteps(
+    mut commands: Commands,
+    mut animation_events: EventReader<AnimationEvent>,
+    duck_assets: Res<DuckAssets>,
+    players: Query<(), With<Player>>,
+) {
+    for e in animation_events.read() {
+        if e.name != FOOTSTEP || !players.contains(e.entity) {
+            continue;
+        }
+        let rng = &mut rand::thread_rng();
+        let Some(random_step) = duck_assets.steps.choose(rng) else {
+            continue;
+        };
+        commands.play_sound_effect(AudioBundle {
+            source: random_step.clone(),
+            settings: PlaybackSettings::DESPAWN,
+        });
+    }
+}
+
+/// One of other ducks' steps, while it plays.
+#[derive(Component)]
+struct RemoteFootstep;
+
+/// Other ducks' steps come from them, and follow them as they walk. Closer
+/// steps take over from the farthest ones once [`MAX_REMOTE_FOOTSTEPS`] play.
+fn play_other_player_footsteps(
+    mut commands: Commands,
+    mut animation_events: EventReader<AnimationEvent>,
+    duck_assets: Res<DuckAssets>,
+    players: Query<&GlobalTransform, With<Player>>,
+    other_players: Query<&GlobalTransform, With<OtherPlayer>>,
+    footsteps: Query<(Entity, &Parent), With<RemoteFootstep>>,
+) {
+    // Without our duck there's no listener to hear them with.
+    let Ok(listener) = players.get_single() else {
+        animation_events.clear();
+        return;
+    };
+    let listener = listener.translation().truncate();
+    let distance_to = |duck: Entity| {
+        other_players
+            .get(duck)
+            .ok()
+            .map(|transform| transform.translation().truncate().distance(listener))
+    };
+
+    let mut steps: Vec<(Entity, f32)> = animation_events
+        .read()
+        .filter(|e| e.name == FOOTSTEP)
+        .filter_map(|e| Some((e.entity, distance_to(e.entity)?)))
+        .filter(|&(_, distance)| distance <= FOOTSTEP_HEARING_DISTANCE)
+        .collect();
+    if steps.is_empty() {
+        return;
+    }
+    steps.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+
+    // What's playing, closest first.
+    let mut playing: Vec<(Entity, f32)> = footsteps
+        .iter()
+        .map(|(step, duck)| (step, distance_to(duck.get()).unwrap_or(f32::INFINITY)))
+        .collect();
+    playing.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+
+    for (duck, distance) in steps {
+        if playing.len() >= MAX_REMOTE_FOOTSTEPS {
+            match playing.last() {
+                Some(&(farthest, farthest_distance)) if farthest_distance > distance => {
+                    commands.entity(farthest).despawn_recursive();
+                    playing.pop();
+                }
+                // The rest are even further away.
+                _ => break,
+            }
+        }
+
+        let rng = &mut rand::thread_rng();
+        let Some(random_step) = duck_assets.steps.choose(rng) else {
+            continue;
+        };
+        let step = commands
+            .play_sound_effect_on(
+                duck,
+                AudioBundle {
+                    source: random_step.clone(),
+                    settings: PlaybackSettings::DESPAWN,
+                },
+            )
+            .insert(RemoteFootstep)
+            .id();
+        let index = playing.partition_point(|&(_, d)| d <= distance);
+        playing.insert(index, (step, distance));
+    }
+}
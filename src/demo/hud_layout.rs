@@ -0,0 +1,122 @@
+//! Keeps the HUD usable on any window shape.
+//!
+//! The window can be any size: a resized desktop window, or a phone that gets
+//! rotated. [`HudLayout`] tracks whether the window is in portrait or landscape,
+//! HUD nodes with a [`ResponsiveHud`] are moved to the matching position, and
+//! [`UiScale`] grows or shrinks the whole HUD with the window's short side.
+
+use bevy::{prelude::*, window::PrimaryWindow};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<HudLayout>();
+    app.add_systems(
+        Update,
+        (update_hud_layout, apply_responsive_hud).chain(),
+    );
+}
+
+/// The short side of the window the HUD was designed for, in logical pixels.
+const REFERENCE_SHORT_SIDE: f32 = 600.0;
+const MIN_UI_SCALE: f32 = 0.7;
+const MAX_UI_SCALE: f32 = 1.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScreenOrientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+#[derive(Resource, Debug, Default, PartialEq)]
+pub struct HudLayout {
+    pub orientation: ScreenOrientation,
+}
+
+/// Where a HUD node sits in each orientation. Applied to the node's `left`,
+/// `right`, `top` and `bottom`, so use [`Val::Auto`] for the sides it isn't
+/// anchored to.
+#[derive(Component, Debug, Clone)]
+pub struct ResponsiveHud {
+    pub portrait: UiRect,
+    pub landscape: UiRect,
+}
+
+impl ResponsiveHud {
+    /// Anchored to the top-left corner.
+    pub fn top_left(portrait: (Val, Val), landscape: (Val, Val)) -> Self {
+        Self {
+            portrait: UiRect::new(portrait.0, Val::Auto, portrait.1, Val::Auto),
+            landscape: UiRect::new(landscape.0, Val::Auto, landscape.1, Val::Auto),
+        }
+    }
+
+    /// Anchored to the top-right corner.
+    pub fn top_right(portrait: (Val, Val), landscape: (Val, Val)) -> Self {
+        Self {
+            portrait: UiRect::new(Val::Auto, portrait.0, portrait.1, Val::Auto),
+            landscape: UiRect::new(Val::Auto, landscape.0, landscape.1, Val::Auto),
+        }
+    }
+
+    /// Anchored to the bottom-right corner.
+    pub fn bottom_right(portrait: (Val, Val), landscape: (Val, Val)) -> Self {
+        Self {
+            portrait: UiRect::new(Val::Auto, portrait.0, Val::Auto, portrait.1),
+            landscape: UiRect::new(Val::Auto, landscape.0, Val::Auto, landscape.1),
+        }
+    }
+
+    fn for_orientation(&self, orientation: ScreenOrientation) -> UiRect {
+        match orientation {
+            ScreenOrientation::Portrait => self.portrait,
+            ScreenOrientation::Landscape => self.landscape,
+        }
+    }
+}
+
+fn update_hud_layout(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut hud_layout: ResMut<HudLayout>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let (width, height) = (window.width(), window.height());
+    if width <= 0.0 || height <= 0.0 {
+        // Minimized, or the canvas isn't laid out yet.
+        return;
+    }
+
+    let orientation = if width > height {
+        ScreenOrientation::Landscape
+    } else {
+        ScreenOrientation::Portrait
+    };
+    if hud_layout.set_if_neq(HudLayout { orientation }) {
+        info!("Laying the HUD out for {:?}", orientation);
+    }
+
+    let scale = (width.min(height) / REFERENCE_SHORT_SIDE).clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+    // Ignore tiny changes so dragging a window edge doesn't relayout every frame.
+    if (ui_scale.0 - scale).abs() > 0.01 {
+        ui_scale.0 = scale;
+    }
+}
+
+fn apply_responsive_hud(
+    hud_layout: Res<HudLayout>,
+    mut nodes: Query<(Ref<ResponsiveHud>, &mut Style)>,
+) {
+    for (responsive_hud, mut style) in &mut nodes {
+        if !hud_layout.is_changed() && !responsive_hud.is_added() {
+            continue;
+        }
+
+        let position = responsive_hud.for_orientation(hud_layout.orientation);
+        style.left = position.left;
+        style.right = position.right;
+        style.top = position.top;
+        style.bottom = position.bottom;
+    }
+}
@@ -0,0 +1,226 @@
+//! Area-of-interest culling for other players.
+//!
+//! Most of the pond is off-screen at any time, so ducks outside the camera
+//! view (plus a margin) are hidden and marked [`OutOfView`], which stops their
+//! animation and step sounds. Off-screen ducks get an arrow at the edge of the
+//! screen pointing their way. If the server advertises
+//! [`ProtocolFeature::InterestArea`] we also tell it what we can see, so it can
+//! send fewer updates about far away ducks.
+
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle, utils::HashMap};
+
+use crate::{screens::Screen, AppSet};
+
+use super::{
+    other_player::OtherPlayer,
+    outbound_queue::OutboundQueue,
+    protocol::{ProtocolFeature, ServerProtocol},
+    rate_limit::OutboundRateLimiter,
+    websocket_connect::C2SActionTypes,
+};
+
+/// How far outside the camera view a duck can be and still count as in view,
+/// so ducks don't pop in right at the edge of the screen.
+const INTEREST_MARGIN: f32 = 200.0;
+
+/// How far the view has to move before we tell the server again.
+const VIEW_RECT_RESEND_DISTANCE: f32 = 100.0;
+
+/// How far from the edge of the screen the off-screen arrows sit.
+const ARROW_INSET: f32 = 30.0;
+const ARROW_SIZE: f32 = 14.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<InterestArea>();
+    app.add_systems(
+        Update,
+        (
+            update_interest_area,
+            (cull_other_players, update_offscreen_arrows, send_view_rect),
+        )
+            .chain()
+            .in_set(AppSet::Update)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// What the camera can see, in world coordinates.
+#[derive(Resource, Debug, Default)]
+pub struct InterestArea {
+    /// Exactly what is on screen.
+    pub view: Rect,
+    /// The view plus [`INTEREST_MARGIN`] on every side.
+    pub interest: Rect,
+}
+
+/// Marks another player's duck that is too far away to bother animating.
+#[derive(Component, Debug)]
+pub struct OutOfView;
+
+/// An arrow at the edge of the screen pointing at an off-screen duck.
+#[derive(Component, Debug)]
+struct OffscreenArrow(Entity);
+
+#[derive(serde::Serialize)]
+struct ViewRectRequestData {
+    min_x: f32,
+    min_y: f32,
+    max_x: f32,
+    max_y: f32,
+}
+
+#[derive(serde::Serialize)]
+struct ViewRectRequest {
+    action_type: String,
+    data: ViewRectRequestData,
+}
+
+fn build_view_rect_request_msg(rect: Rect) -> String {
+    let view_rect_request = ViewRectRequest {
+        action_type: "view_rect".to_string(),
+        data: ViewRectRequestData {
+            min_x: rect.min.x,
+            min_y: rect.min.y,
+            max_x: rect.max.x,
+            max_y: rect.max.y,
+        },
+    };
+
+    serde_json::ser::to_string(&view_rect_request).unwrap_or_else(|e| {
+        warn!("Couldn't convert view rect request struct to string: {e}");
+        "".to_string()
+    })
+}
+
+fn update_interest_area(
+    mut interest_area: ResMut<InterestArea>,
+    cameras: Query<(&Transform, &OrthographicProjection), With<Camera2d>>,
+) {
+    let Ok((transform, projection)) = cameras.get_single() else {
+        return;
+    };
+    let center = transform.translation.truncate() + projection.area.center();
+    let view = Rect::from_center_size(center, projection.area.size());
+
+    if interest_area.view != view {
+        interest_area.view = view;
+        interest_area.interest = view.inflate(INTEREST_MARGIN);
+    }
+}
+
+fn cull_other_players(
+    mut commands: Commands,
+    interest_area: Res<InterestArea>,
+    mut other_players: Query<(Entity, &Transform, &mut Visibility, Has<OutOfView>), With<OtherPlayer>>,
+) {
+    for (entity, transform, mut visibility, out_of_view) in &mut other_players {
+        let in_view = interest_area
+            .interest
+            .contains(transform.translation.truncate());
+
+        if in_view && out_of_view {
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).remove::<OutOfView>();
+        } else if !in_view && !out_of_view {
+            *visibility = Visibility::Hidden;
+            commands.entity(entity).insert(OutOfView);
+        }
+    }
+}
+
+fn update_offscreen_arrows(
+    mut commands: Commands,
+    interest_area: Res<InterestArea>,
+    other_players: Query<(Entity, &Transform, &Sprite), (With<OtherPlayer>, Without<OffscreenArrow>)>,
+    mut arrows: Query<(Entity, &OffscreenArrow, &mut Transform), Without<OtherPlayer>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let view = interest_area.view;
+    let center = view.center();
+    let half_size = (view.half_size() - Vec2::splat(ARROW_INSET)).max(Vec2::ONE);
+
+    let mut arrows_by_target: HashMap<Entity, Entity> = HashMap::default();
+    for (arrow_entity, arrow, _) in &arrows {
+        if other_players.contains(arrow.0) {
+            arrows_by_target.insert(arrow.0, arrow_entity);
+        } else {
+            // The duck left the pond.
+            commands.entity(arrow_entity).despawn_recursive();
+        }
+    }
+
+    for (entity, transform, sprite) in &other_players {
+        let position = transform.translation.truncate();
+        let arrow_entity = arrows_by_target.get(&entity).copied();
+
+        if view.contains(position) {
+            if let Some(arrow_entity) = arrow_entity {
+                commands.entity(arrow_entity).despawn_recursive();
+            }
+            continue;
+        }
+
+        // Walk from the middle of the screen towards the duck until we hit the edge.
+        let direction = position - center;
+        let scale = (half_size.x / direction.x.abs()).min(half_size.y / direction.y.abs());
+        let arrow_transform = Transform {
+            translation: (center + direction * scale).extend(200.0),
+            rotation: Quat::from_rotation_z(direction.y.atan2(direction.x)),
+            ..default()
+        };
+
+        match arrow_entity.and_then(|arrow_entity| arrows.get_mut(arrow_entity).ok()) {
+            Some((_, _, mut transform)) => *transform = arrow_transform,
+            None => {
+                commands.spawn((
+                    Name::new("Offscreen Arrow"),
+                    OffscreenArrow(entity),
+                    MaterialMesh2dBundle {
+                        mesh: meshes
+                            .add(Triangle2d::new(
+                                Vec2::new(ARROW_SIZE, 0.0),
+                                Vec2::new(-ARROW_SIZE / 2.0, ARROW_SIZE / 2.0),
+                                Vec2::new(-ARROW_SIZE / 2.0, -ARROW_SIZE / 2.0),
+                            ))
+                            .into(),
+                        material: materials.add(sprite.color),
+                        transform: arrow_transform,
+                        ..default()
+                    },
+                    StateScoped(Screen::Gameplay),
+                ));
+            }
+        }
+    }
+}
+
+/// Tell the server what we can see whenever the view has moved far enough.
+fn send_view_rect(
+    interest_area: Res<InterestArea>,
+    server_protocol: Res<ServerProtocol>,
+    mut rate_limiter: ResMut<OutboundRateLimiter>,
+    time: Res<Time>,
+    mut outbound_queues: Query<&mut OutboundQueue>,
+    mut last_sent: Local<Option<Rect>>,
+) {
+    // Nobody to tell, so make sure the next connection hears about it.
+    if outbound_queues.is_empty() || !server_protocol.has_feature(ProtocolFeature::InterestArea) {
+        *last_sent = None;
+        return;
+    }
+
+    let interest = interest_area.interest;
+    let moved_enough = last_sent.is_none_or(|last_sent| {
+        last_sent.min.distance(interest.min) > VIEW_RECT_RESEND_DISTANCE
+            || last_sent.max.distance(interest.max) > VIEW_RECT_RESEND_DISTANCE
+    });
+    if !moved_enough || !rate_limiter.try_acquire(C2SActionTypes::ViewRect, time.elapsed()) {
+        return;
+    }
+
+    for mut queue in &mut outbound_queues {
+        queue.push(C2SActionTypes::ViewRect, build_view_rect_request_msg(interest));
+    }
+    *last_sent = Some(interest);
+}
@@ -1,146 +1,458 @@
-// Yeah, basically not even using this file anymore, but it
-// is kinda nice to have this example of a custom command
-// bc tbh I still don't really understand when to use those...
+//! Levels are described by `.level.json` assets in `assets/levels`: the world
+//! bounds, layered decorations, spawn points, collision shapes, hazards and
+//! ambient sounds.
+//!
+//! The default level and the ambient sounds are loaded along with everything
+//! else on the loading screen. The server can ask for a different level by
+//! name in its `you_joined` message, and editing a level file while the game
+//! runs (native dev builds) swaps it in on the fly.
 
-use std::sync::Arc;
+use bevy::{asset::LoadState, audio::Volume, prelude::*};
+use serde::Deserialize;
 
-use bevy::{
-    app::{App, Startup, Update}, asset::AssetServer, prelude::{Res, Commands, Component, Entity, EventReader, PerspectiveProjection, Query, ResMut, Resource, With, World}, utils::info, window::WindowResized
+use crate::{
+    asset_manifest::AssetManifest, asset_tracking::LoadResource, audio::AudioCommands,
+    json_asset::RegisterJsonAsset, screens::Screen,
 };
 
-use futures::lock::Mutex;
-use wasm_bindgen_futures::spawn_local;
+use super::{
+    background::spawn_decoration,
+    camera::CameraController,
+    collision::{Collider, Obstacle},
+    movement::{MovementController, WorldBounds},
+    player::Player,
+    websocket_connect::YouJoinedWsReceived,
+};
+
+/// The level we play on unless the server says otherwise.
+const DEFAULT_LEVEL_NAME: &str = "pond";
 
-use super::check_silent_mode;
+/// Ambient sounds loaded up front, by their name in the asset manifest. Levels
+/// can use others, but those only start loading once the level spawns.
+const AMBIENCE_SOUNDS: [&str; 3] = ["ambient_water", "ambient_birds", "ambient_wind"];
 
 pub(super) fn plugin(_app: &mut App) {
-    // _app.add_systems(Startup, init_camera_ratio);
-    _app.add_systems(Update, adjust_camera_ratio);
+    // Window resizing is handled by the camera's scaling mode (`camera.rs`) and `hud_layout.rs`.
+
+    _app.register_json_asset::<LevelDefinition>(&["level.json"]);
+    _app.load_resource::<LevelAssets>();
+    _app.init_resource::<CurrentLevel>();
+    _app.add_systems(
+        Update,
+        (
+            (switch_to_server_level, respawn_level_on_change),
+            // Gameplay can start before the default level has loaded.
+            respawn_level.run_if(resource_exists::<LevelAssets>),
+            apply_hazards,
+        )
+            .chain()
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+fn level_path(name: &str) -> String {
+    format!("levels/{name}.level.json")
+}
+
+/// A level, as stored in a `.level.json` asset.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct LevelDefinition {
+    pub name: String,
+    /// Where ducks can walk.
+    pub bounds: LevelBounds,
+    /// Images drawn around the level, back to front by `layer`.
+    #[serde(default)]
+    pub decorations: Vec<LevelDecoration>,
+    /// Where the camera waits until the server tells us where our duck is.
+    #[serde(default)]
+    pub spawn_points: Vec<Vec2>,
+    /// Solid things ducks can't walk through.
+    #[serde(default)]
+    pub colliders: Vec<LevelShape>,
+    #[serde(default)]
+    pub hazards: Vec<LevelHazard>,
+    /// Sounds that loop at a spot in the level, heard from wherever our duck is.
+    #[serde(default)]
+    pub ambience: Vec<LevelAmbience>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct LevelBounds {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct LevelDecoration {
+    pub name: String,
+    /// Path of the image, relative to the `assets` folder.
+    pub image: String,
+    pub position: Vec2,
+    /// The z coordinate. Ducks walk around at `10.0`, so anything above that
+    /// is drawn in front of them.
+    #[serde(default)]
+    pub layer: f32,
+    /// How far it scrolls for every unit the camera moves. `1.0` stays put in
+    /// the world, less looks further away and more looks closer to the camera.
+    #[serde(default = "default_parallax")]
+    pub parallax: Vec2,
+    /// How fast it floats across the level, e.g. for clouds. It wraps around
+    /// to the other side once it's well past the world bounds.
+    #[serde(default)]
+    pub drift: Vec2,
+    /// Play `image` as a sprite sheet instead of showing all of it.
+    #[serde(default)]
+    pub animation: Option<DecorationSpriteSheet>,
+    /// Rock back and forth around the bottom of the image, e.g. for trees.
+    #[serde(default)]
+    pub sway: Option<DecorationSway>,
+}
+
+fn default_parallax() -> Vec2 {
+    Vec2::ONE
+}
+
+/// A sprite sheet of equally sized frames, played in a loop left to right,
+/// top to bottom.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct DecorationSpriteSheet {
+    pub frame_size: UVec2,
+    pub columns: u32,
+    #[serde(default = "default_rows")]
+    pub rows: u32,
+    pub frames_per_second: f32,
+}
+
+fn default_rows() -> u32 {
+    1
+}
+
+/// How far and how slowly a decoration sways, e.g. `{"degrees": 0.3, "seconds": 6.0}`.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct DecorationSway {
+    /// The furthest it leans either way.
+    pub degrees: f32,
+    /// How long swaying there and back takes.
+    pub seconds: f32,
+}
+
+/// An area of the level, e.g. `{"shape": "rect", "center": [0, 0], "size": [100, 50]}`
+/// or `{"shape": "circle", "center": [0, 0], "radius": 30}`.
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(tag = "shape", rename_all = "snake_case")]
+pub enum LevelShape {
+    Rect { center: Vec2, size: Vec2 },
+    Circle { center: Vec2, radius: f32 },
+}
+
+impl LevelShape {
+    pub fn contains(&self, point: Vec2) -> bool {
+        match self {
+            LevelShape::Rect { center, size } => {
+                Rect::from_center_size(*center, *size).contains(point)
+            }
+            LevelShape::Circle { center, radius } => center.distance(point) <= *radius,
+        }
+    }
 
-    _app.add_systems(Startup, check_for_silent_mode_once);
+    /// A collider for this shape, and where to put it.
+    pub fn to_collider(self) -> (Collider, Vec2) {
+        match self {
+            LevelShape::Rect { center, size } => (
+                Collider::Aabb {
+                    half_size: size / 2.0,
+                },
+                center,
+            ),
+            LevelShape::Circle { center, radius } => (Collider::Circle { radius }, center),
+        }
+    }
+}
 
-    _app.insert_resource(SilentModeStatus { is_silent: Arc::new(Mutex::new(None)) });
+/// An area that slows down ducks walking through it, like mud or reeds.
+#[derive(Component, Deserialize, Debug, Clone)]
+pub struct LevelHazard {
+    pub name: String,
+    pub area: LevelShape,
+    /// How fast ducks move inside, as a fraction of their normal speed.
+    pub speed_multiplier: f32,
+}
 
-    // _app.add_systems(Update, display_popup_if_silent);
-    // _app.add_systems(Update, display_popup_if_silent.system());
+/// A sound like water lapping or birds in the trees, e.g.
+/// `{"name": "Birds", "sound": "ambient_birds", "position": [-900, 0]}`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct LevelAmbience {
+    pub name: String,
+    /// Name of the sound in the asset manifest. It loops forever.
+    pub sound: String,
+    pub position: Vec2,
+    /// How loud it is right next to it, from `0.0` to `1.0`.
+    #[serde(default = "default_ambience_volume")]
+    pub volume: f32,
 }
 
+fn default_ambience_volume() -> f32 {
+    1.0
+}
+
+#[derive(Resource, Asset, Reflect, Clone)]
+pub struct LevelAssets {
+    #[dependency]
+    pub default_level: Handle<LevelDefinition>,
+    /// One per sound in [`AMBIENCE_SOUNDS`], in the same order.
+    #[dependency]
+    ambience: Vec<Handle<AudioSource>>,
+}
+
+impl LevelAssets {
+    /// The ambient sound called `name`, loading it now if it wasn't preloaded.
+    fn ambience(
+        &self,
+        name: &str,
+        manifest: &AssetManifest,
+        assets: &AssetServer,
+    ) -> Handle<AudioSource> {
+        match AMBIENCE_SOUNDS.iter().position(|sound| *sound == name) {
+            Some(index) => self.ambience[index].clone(),
+            None => {
+                warn!("Ambient sound {:?} wasn't preloaded, loading it now", name);
+                manifest.load_sound(assets, name)
+            }
+        }
+    }
+}
+
+impl FromWorld for LevelAssets {
+    fn from_world(world: &mut World) -> Self {
+        let manifest = world.resource::<AssetManifest>();
+        let assets = world.resource::<AssetServer>();
+        Self {
+            default_level: assets.load(level_path(DEFAULT_LEVEL_NAME)),
+            ambience: AMBIENCE_SOUNDS
+                .iter()
+                .map(|sound| manifest.load_sound(assets, sound))
+                .collect(),
+        }
+    }
+}
+
+/// The level we are playing on, and whether it has been spawned yet.
+#[derive(Resource, Debug)]
+pub struct CurrentLevel {
+    pub name: String,
+    pub handle: Handle<LevelDefinition>,
+    spawned: bool,
+}
+
+impl FromWorld for CurrentLevel {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            name: DEFAULT_LEVEL_NAME.to_string(),
+            handle: assets.load(level_path(DEFAULT_LEVEL_NAME)),
+            spawned: false,
+        }
+    }
+}
+
+/// Everything spawned from the current level, so it can be swapped out.
+#[derive(Component, Debug)]
+struct LevelEntity;
+
 /// A [`Command`] to spawn the level.s
 /// Functions that accept only `&mut World` as their parameter implement [`Command`].
 /// We use this style when a command requires no configuration.
-pub fn spawn_level(_world: &mut World) {
-    // The only thing we have in our level is a player,
-    // but add things like walls etc. here.
+pub fn spawn_level(world: &mut World) {
+    // The level entities are state scoped, so they are already gone if we played before.
+    world.resource_mut::<CurrentLevel>().spawned = false;
+}
 
-    // SpawnPlayer { max_speed: 400.0 }.apply(world);
+#[derive(Deserialize)]
+struct YouJoinedLevelData {
+    #[serde(default)]
+    map: Option<String>,
 }
 
-// pub fn init_camera_ratio(mut commands: Commands) {
-//         commands.spawn_bundle(PerspectiveCameraBundle {
-//             perspective_projection: PerspectiveProjection {
-//                 aspect_ratio: 16.0 / 9.0, // Initial aspect ratio
-//                 ..Default::default()
-//             },
-//             ..Default::default()
-//         });
+/// Load the level the server asked for, if it isn't the one we have.
+fn switch_to_server_level(
+    mut event_reader: EventReader<YouJoinedWsReceived>,
+    mut current_level: ResMut<CurrentLevel>,
+    asset_server: Res<AssetServer>,
+) {
+    for e in event_reader.read() {
+        let Some(map) = serde_json::from_value::<YouJoinedLevelData>(e.data.clone())
+            .ok()
+            .and_then(|data| data.map)
+        else {
+            continue;
+        };
 
-// }
+        // The name ends up in an asset path, so don't let it wander off.
+        if map.is_empty()
+            || !map
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            warn!("Ignoring invalid map name from server: {:?}", map);
+            continue;
+        }
+        if map == current_level.name {
+            continue;
+        }
 
-pub fn adjust_camera_ratio(
-    mut resize_events: EventReader<WindowResized>,
-    mut query: Query<&mut PerspectiveProjection>,
+        info!("Server asked for map {}", map);
+        current_level.handle = asset_server.load(level_path(&map));
+        current_level.name = map;
+        current_level.spawned = false;
+    }
+}
+
+/// Respawn the level whenever its file changes on disk.
+fn respawn_level_on_change(
+    mut asset_events: EventReader<AssetEvent<LevelDefinition>>,
+    mut current_level: ResMut<CurrentLevel>,
 ) {
-    for event in resize_events.read() {
-        for mut projection in query.iter_mut() {
-            println!("adjusting camera");
-            projection.aspect_ratio = event.width / event.height;
+    for e in asset_events.read() {
+        if e.is_modified(&current_level.handle) {
+            info!("Level {} changed, respawning it", current_level.name);
+            current_level.spawned = false;
         }
     }
 }
 
-// This resource will store the silent mode status
-#[derive(Default, Resource)]
-pub struct SilentModeStatus {
-    // is_silent: Option<bool>,
-    is_silent: Arc<Mutex<Option<bool>>>,
+fn respawn_level(
+    mut commands: Commands,
+    mut current_level: ResMut<CurrentLevel>,
+    level_assets: Res<LevelAssets>,
+    levels: Res<Assets<LevelDefinition>>,
+    asset_server: Res<AssetServer>,
+    manifest: Res<AssetManifest>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut world_bounds: ResMut<WorldBounds>,
+    level_entities: Query<Entity, With<LevelEntity>>,
+    players: Query<(), With<Player>>,
+    mut cameras: Query<&mut CameraController>,
+) {
+    if current_level.spawned {
+        return;
+    }
+
+    let Some(level) = levels.get(&current_level.handle) else {
+        if let Some(LoadState::Failed(error)) = asset_server.get_load_state(&current_level.handle) {
+            warn!(
+                "Could not load level {}, using {} instead: {}",
+                current_level.name, DEFAULT_LEVEL_NAME, error
+            );
+            current_level.name = DEFAULT_LEVEL_NAME.to_string();
+            current_level.handle = level_assets.default_level.clone();
+        }
+        return;
+    };
+
+    info!(
+        "Spawning level {} with {} decorations, {} colliders, {} hazards and {} ambient sounds",
+        level.name,
+        level.decorations.len(),
+        level.colliders.len(),
+        level.hazards.len(),
+        level.ambience.len()
+    );
+
+    for entity in &level_entities {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    world_bounds.0 = Rect::from_corners(level.bounds.min, level.bounds.max);
+
+    for decoration in &level.decorations {
+        let entity = spawn_decoration(
+            &mut commands,
+            &asset_server,
+            &manifest,
+            &mut texture_atlas_layouts,
+            decoration,
+        );
+        commands
+            .entity(entity)
+            .insert((LevelEntity, StateScoped(Screen::Gameplay)));
+    }
+
+    for shape in &level.colliders {
+        let (collider, position) = shape.to_collider();
+        commands.spawn((
+            Name::new("Collider"),
+            collider,
+            Obstacle,
+            TransformBundle::from_transform(Transform::from_translation(position.extend(0.0))),
+            LevelEntity,
+            StateScoped(Screen::Gameplay),
+        ));
+    }
+
+    for hazard in &level.hazards {
+        commands.spawn((
+            Name::new(hazard.name.clone()),
+            hazard.clone(),
+            LevelEntity,
+            StateScoped(Screen::Gameplay),
+        ));
+    }
+
+    for ambience in &level.ambience {
+        commands
+            .play_sound_effect_at(
+                ambience.position,
+                AudioBundle {
+                    source: level_assets.ambience(&ambience.sound, &manifest, &asset_server),
+                    settings: PlaybackSettings::LOOP
+                        .with_volume(Volume::new(ambience.volume.clamp(0.0, 1.0))),
+                },
+            )
+            .insert((
+                Name::new(ambience.name.clone()),
+                LevelEntity,
+                StateScoped(Screen::Gameplay),
+            ));
+    }
+
+    // Until our duck shows up, look at where it will probably appear.
+    if players.is_empty() {
+        if let Some(spawn_point) = level.spawn_points.first() {
+            for mut controller in &mut cameras {
+                controller.look_at(*spawn_point);
+            }
+        }
+    }
+
+    current_level.spawned = true;
 }
 
-// fn check_for_silent_mode_once(mut silent_mode_status: ResMut<SilentModeStatus>) {
-//     spawn_local(async move {
-//         let is_silent = check_silent_mode::check_silent_mode().await;
-//         silent_mode_status.is_silent = Some(is_silent);
-//     });
-// }
+/// Slow down ducks walking through hazards.
+fn apply_hazards(
+    hazards: Query<&LevelHazard>,
+    mut ducks: Query<(&mut MovementController, &Transform)>,
+) {
+    for (mut controller, transform) in &mut ducks {
+        let position = transform.translation.truncate();
+        let speed_multiplier = hazards
+            .iter()
+            .filter(|hazard| hazard.area.contains(position))
+            .map(|hazard| hazard.speed_multiplier)
+            .product::<f32>();
 
-fn check_for_silent_mode_once(silent_mode_status: Res<SilentModeStatus>) {
-    let shared_status = silent_mode_status.is_silent.clone();
-    spawn_local(check_silent_mode::check_silent_mode(shared_status));
-}
-
-#[derive(Component)]
-struct SilentModePopup;
-
-// fn display_popup_if_silent(
-//     mut commands: Commands,
-//     silent_mode_status: Res<SilentModeStatus>,
-//     query: Query<Entity, With<SilentModePopup>>,
-//     asset_server: Res<AssetServer>,
-// ) {
-//     // Lock the shared status to access the silent mode value
-//     let is_silent = *silent_mode_status.is_silent.lock();
-
-//     if let Some(is_silent) = is_silent {
-//         // if is_silent && query.is_empty() {
-//         //     commands.spawn_bundle(TextBundle {
-//         //         style: Style {
-//         //             margin: Rect::all(Val::Px(5.0)),
-//         //             ..Default::default()
-//         //         },
-//         //         text: Text::with_section(
-//         //             "Silent Mode is ON!",
-//         //             TextStyle {
-//         //                 font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-//         //                 font_size: 30.0,
-//         //                 color: Color::WHITE,
-//         //             },
-//         //             Default::default(),
-//         //         ),
-//         //         ..Default::default()
-//         //     })
-//         //     .insert(SilentModePopup);
-//         // }
-//     }
-// }
+        if controller.speed_multiplier != speed_multiplier {
+            controller.speed_multiplier = speed_multiplier;
+        }
+    }
+}
+
+// pub fn init_camera_ratio(mut commands: Commands) {
+//         commands.spawn_bundle(PerspectiveCameraBundle {
+//             perspective_projection: PerspectiveProjection {
+//                 aspect_ratio: 16.0 / 9.0, // Initial aspect ratio
+//                 ..Default::default()
+//             },
+//             ..Default::default()
+//         });
 
-// #[derive(Component)]
-// struct SilentModePopup;
-
-// fn display_popup_if_silent(
-//     mut commands: Commands,
-//     silent_mode_status: Res<SilentModeStatus>,
-//     query: Query<Entity, With<SilentModePopup>>,
-//     asset_server: Res<AssetServer>,
-// ) {
-//     if let Some(is_silent) = silent_mode_status.is_silent {
-//         if is_silent && query.is_empty() {
-//             commands
-//                 .spawn_bundle(TextBundle {
-//                     style: Style {
-//                         margin: Rect::all(Val::Px(5.0)),
-//                         ..Default::default()
-//                     },
-//                     text: Text::with_section(
-//                         "Silent Mode is ON!",
-//                         TextStyle {
-//                             font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-//                             font_size: 30.0,
-//                             color: Color::WHITE,
-//                         },
-//                         Default::default(),
-//                     ),
-//                     ..Default::default()
-//                 })
-//                 .insert(SilentModePopup);
-//         }
-//     }
 // }
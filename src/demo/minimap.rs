@@ -0,0 +1,578 @@
+//! A minimap of the whole pond in the corner of the screen.
+//!
+//! The map is drawn from the world bounds in [`movement`](super::movement) and
+//! shows our duck, the other ducks in their colors, the cracker and whoever is
+//! leading the leaderboard. `M` shows and hides it, `-` and `=` resize it, and
+//! the buttons above it do the same on mobile. Tapping the map pings that spot
+//! for a few seconds, and if the server advertises [`ProtocolFeature::Ping`]
+//! the other players see the ping too.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle, ui::RelativeCursorPosition, utils::HashMap};
+use serde::Deserialize;
+
+use crate::{
+    music::MusicMood,
+    screens::Screen,
+    theme::{
+        palette::{BUTTON_HOVERED_BACKGROUND, BUTTON_PRESSED_BACKGROUND, BUTTON_TEXT, NODE_BACKGROUND},
+        prelude::*,
+    },
+    AppSet,
+};
+
+use super::{
+    cracker::Cracker,
+    hud_layout::ResponsiveHud,
+    interest::InterestArea,
+    movement::WorldBounds,
+    other_player::{unpack_duck_color, OtherPlayer},
+    outbound_queue::OutboundQueue,
+    player::Player,
+    player_registry::PlayerRegistry,
+    protocol::{ProtocolFeature, ServerProtocol},
+    rate_limit::OutboundRateLimiter,
+    score::LeaderboardUpdateData,
+    websocket_connect::{C2SActionTypes, UpdateLeaderboardBevyEvent},
+};
+
+/// The side lengths the map cycles through, in pixels.
+const MINIMAP_SIZES: [f32; 3] = [120.0, 180.0, 260.0];
+
+const MINIMAP_BACKGROUND: Color = Color::srgba(0.0, 0.0, 0.0, 0.45);
+const CRACKER_DOT_COLOR: Color = Color::srgb(0.85, 0.65, 0.3);
+const LEADER_BORDER_COLOR: Color = Color::srgb(1.0, 0.84, 0.0);
+
+const PING_DURATION: Duration = Duration::from_secs(3);
+const PING_RADIUS: f32 = 40.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<MinimapSettings>();
+    app.init_resource::<LeaderboardLeader>();
+    app.add_event::<OtherPlayerPingedBevyEvent>();
+
+    app.add_systems(OnEnter(Screen::Gameplay), spawn_minimap);
+    app.add_systems(
+        Update,
+        (
+            (
+                minimap_keyboard_controls,
+                track_leaderboard_leader,
+                other_player_pinged_handler,
+            ),
+            (apply_minimap_settings, update_minimap_dots, update_pings),
+        )
+            .chain()
+            .in_set(AppSet::Update)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Whether the minimap is showing and how big it is. Kept across games.
+#[derive(Resource, Debug)]
+pub struct MinimapSettings {
+    pub visible: bool,
+    /// Index into [`MINIMAP_SIZES`].
+    pub size: usize,
+}
+
+impl Default for MinimapSettings {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            size: 1,
+        }
+    }
+}
+
+impl MinimapSettings {
+    fn side_length(&self) -> f32 {
+        MINIMAP_SIZES[self.size.min(MINIMAP_SIZES.len() - 1)]
+    }
+}
+
+/// Whoever is in first place on the last leaderboard update.
+#[derive(Resource, Debug, Default)]
+pub struct LeaderboardLeader {
+    pub friendly_name: Option<String>,
+    pub is_you: bool,
+}
+
+/// Sent when the server passes on another player's ping.
+#[derive(Event, Debug, Clone, Deserialize)]
+pub struct OtherPlayerPingedBevyEvent {
+    pub player_uuid: String,
+    pub x_position: f32,
+    pub y_position: f32,
+}
+
+/// The map itself. Tapping it pings the spot under the cursor.
+#[derive(Component, Debug)]
+struct Minimap;
+
+/// A dot on the map following something in the world.
+#[derive(Component, Debug)]
+struct MinimapDot(Entity);
+
+/// The outline on the map of what the camera can see.
+#[derive(Component, Debug)]
+struct MinimapViewRect;
+
+/// A pinged spot in the world, shown on the map until it runs out.
+#[derive(Component, Debug)]
+struct Ping {
+    color: Color,
+    timer: Timer,
+}
+
+#[derive(serde::Serialize)]
+struct PingRequestData {
+    x_position: f32,
+    y_position: f32,
+}
+
+#[derive(serde::Serialize)]
+struct PingRequest {
+    action_type: String,
+    data: PingRequestData,
+}
+
+fn build_ping_request_msg(position: Vec2) -> String {
+    let ping_request = PingRequest {
+        action_type: "ping".to_string(),
+        data: PingRequestData {
+            x_position: position.x,
+            y_position: position.y,
+        },
+    };
+
+    serde_json::ser::to_string(&ping_request).unwrap_or_else(|e| {
+        warn!("Couldn't convert ping request struct to string: {e}");
+        "".to_string()
+    })
+}
+
+/// Where a world position sits on the map, from (0, 0) at the top-left to (1, 1) at the bottom-right.
+fn world_to_minimap(bounds: Rect, position: Vec2) -> Vec2 {
+    let fraction = ((position - bounds.min) / bounds.size()).clamp(Vec2::ZERO, Vec2::ONE);
+    Vec2::new(fraction.x, 1.0 - fraction.y)
+}
+
+fn minimap_to_world(bounds: Rect, normalized: Vec2) -> Vec2 {
+    bounds.min + Vec2::new(normalized.x, 1.0 - normalized.y) * bounds.size()
+}
+
+fn small_button_bundle() -> (ButtonBundle, InteractionPalette) {
+    (
+        ButtonBundle {
+            style: Style {
+                width: Val::Px(36.0),
+                height: Val::Px(28.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: NODE_BACKGROUND.into(),
+            ..default()
+        },
+        InteractionPalette {
+            none: NODE_BACKGROUND,
+            hovered: BUTTON_HOVERED_BACKGROUND,
+            pressed: BUTTON_PRESSED_BACKGROUND,
+        },
+    )
+}
+
+fn small_button_text(text: &str) -> TextBundle {
+    TextBundle::from_section(
+        text,
+        TextStyle {
+            font_size: 18.0,
+            color: BUTTON_TEXT,
+            ..default()
+        },
+    )
+}
+
+fn spawn_minimap(mut commands: Commands, settings: Res<MinimapSettings>) {
+    let side_length = settings.side_length();
+
+    commands
+        .spawn((
+            Name::new("Minimap Root"),
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute, // locks the map to the "HUD"
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(6.),
+                    ..default()
+                },
+                ..default()
+            },
+            // Under the score in portrait, but there's no room for that in landscape.
+            ResponsiveHud {
+                portrait: UiRect::new(Val::Percent(3.), Val::Auto, Val::Percent(11.), Val::Auto),
+                landscape: UiRect::new(Val::Percent(2.), Val::Auto, Val::Auto, Val::Percent(3.)),
+            },
+            StateScoped(Screen::Gameplay),
+        ))
+        .with_children(|children| {
+            children
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(6.),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|children| {
+                    children
+                        .spawn((Name::new("Minimap Toggle Button"), small_button_bundle()))
+                        .with_children(|children| {
+                            children.spawn(small_button_text("Map"));
+                        })
+                        .observe(toggle_minimap);
+                    children
+                        .spawn((Name::new("Minimap Resize Button"), small_button_bundle()))
+                        .with_children(|children| {
+                            children.spawn(small_button_text("+/-"));
+                        })
+                        .observe(resize_minimap);
+                });
+
+            children
+                .spawn((
+                    Name::new("Minimap"),
+                    Minimap,
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(side_length),
+                            height: Val::Px(side_length),
+                            border: UiRect::all(Val::Px(2.)),
+                            display: if settings.visible {
+                                Display::Flex
+                            } else {
+                                Display::None
+                            },
+                            ..default()
+                        },
+                        background_color: MINIMAP_BACKGROUND.into(),
+                        border_color: BUTTON_TEXT.into(),
+                        ..default()
+                    },
+                    RelativeCursorPosition::default(),
+                ))
+                .with_children(|children| {
+                    children.spawn((
+                        Name::new("Minimap View Rect"),
+                        MinimapViewRect,
+                        NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                border: UiRect::all(Val::Px(1.)),
+                                ..default()
+                            },
+                            border_color: BUTTON_TEXT.with_alpha(0.6).into(),
+                            ..default()
+                        },
+                    ));
+                })
+                .observe(ping_minimap);
+        });
+}
+
+fn toggle_minimap(_trigger: Trigger<OnPress>, mut settings: ResMut<MinimapSettings>) {
+    settings.visible = !settings.visible;
+}
+
+fn resize_minimap(_trigger: Trigger<OnPress>, mut settings: ResMut<MinimapSettings>) {
+    settings.visible = true;
+    settings.size = (settings.size + 1) % MINIMAP_SIZES.len();
+}
+
+fn minimap_keyboard_controls(
+    input: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<MinimapSettings>,
+) {
+    if input.just_pressed(KeyCode::KeyM) {
+        settings.visible = !settings.visible;
+    }
+    if input.just_pressed(KeyCode::Equal) {
+        settings.size = (settings.size + 1).min(MINIMAP_SIZES.len() - 1);
+    }
+    if input.just_pressed(KeyCode::Minus) {
+        settings.size = settings.size.saturating_sub(1);
+    }
+}
+
+fn apply_minimap_settings(
+    settings: Res<MinimapSettings>,
+    mut minimaps: Query<&mut Style, With<Minimap>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for mut style in &mut minimaps {
+        style.display = if settings.visible {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        style.width = Val::Px(settings.side_length());
+        style.height = Val::Px(settings.side_length());
+    }
+}
+
+fn track_leaderboard_leader(
+    mut event_reader: EventReader<UpdateLeaderboardBevyEvent>,
+    mut leader: ResMut<LeaderboardLeader>,
+    mut music_mood: ResMut<MusicMood>,
+) {
+    for e in event_reader.read() {
+        let Ok(leaderboard) = serde_json::from_value::<LeaderboardUpdateData>(e.data.clone())
+        else {
+            // score.rs already logs the parse failure.
+            continue;
+        };
+
+        leader.is_you = leaderboard.your_leaderboard_place == 1;
+        music_mood.set_if_neq(if leader.is_you {
+            MusicMood::Leading
+        } else {
+            MusicMood::Calm
+        });
+        leader.friendly_name = Some(leaderboard.leaderboard_name_1st_place)
+            .filter(|friendly_name| !friendly_name.is_empty());
+    }
+}
+
+fn update_minimap_dots(
+    mut commands: Commands,
+    minimaps: Query<Entity, With<Minimap>>,
+    players: Query<(Entity, &Transform, &Sprite), With<Player>>,
+    other_players: Query<(Entity, &Transform, &Sprite), With<OtherPlayer>>,
+    crackers: Query<(Entity, &Transform), With<Cracker>>,
+    pings: Query<(Entity, &Transform, &Ping)>,
+    mut dots: Query<(Entity, &MinimapDot, &mut Style, &mut BackgroundColor, &mut BorderColor)>,
+    mut view_rects: Query<&mut Style, (With<MinimapViewRect>, Without<MinimapDot>)>,
+    leader: Res<LeaderboardLeader>,
+    registry: Res<PlayerRegistry>,
+    interest_area: Res<InterestArea>,
+    world_bounds: Res<WorldBounds>,
+) {
+    let Ok(minimap) = minimaps.get_single() else {
+        return;
+    };
+
+    let leader_entity = if leader.is_you {
+        players.iter().next().map(|(entity, ..)| entity)
+    } else {
+        leader
+            .friendly_name
+            .as_deref()
+            .and_then(|friendly_name| registry.entity_by_friendly_name(friendly_name))
+    };
+
+    // (target, position, color, size, border)
+    let mut wanted: Vec<(Entity, Vec2, Color, f32, Option<Color>)> = vec![];
+    for (entity, transform) in &crackers {
+        wanted.push((entity, transform.translation.truncate(), CRACKER_DOT_COLOR, 7.0, None));
+    }
+    for (entity, transform, ping) in &pings {
+        let fade = 1.0 - ping.timer.fraction();
+        wanted.push((
+            entity,
+            transform.translation.truncate(),
+            ping.color.with_alpha(fade),
+            12.0,
+            Some(BUTTON_TEXT.with_alpha(fade)),
+        ));
+    }
+    for (entity, transform, sprite) in &other_players {
+        wanted.push((entity, transform.translation.truncate(), sprite.color, 6.0, None));
+    }
+    // Our duck goes last so it is drawn on top.
+    for (entity, transform, sprite) in &players {
+        wanted.push((
+            entity,
+            transform.translation.truncate(),
+            sprite.color,
+            9.0,
+            Some(BUTTON_TEXT),
+        ));
+    }
+    for (entity, _, _, size, border) in &mut wanted {
+        if Some(*entity) == leader_entity {
+            *size += 4.0;
+            *border = Some(LEADER_BORDER_COLOR);
+        }
+    }
+
+    let mut dots_by_target: HashMap<Entity, Entity> = HashMap::default();
+    for (dot_entity, dot, ..) in &dots {
+        if wanted.iter().any(|(target, ..)| *target == dot.0) {
+            dots_by_target.insert(dot.0, dot_entity);
+        } else {
+            commands.entity(dot_entity).despawn_recursive();
+        }
+    }
+
+    for (target, position, color, size, border) in wanted {
+        let on_map = world_to_minimap(world_bounds.0, position);
+        let style = Style {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(on_map.x * 100.0),
+            top: Val::Percent(on_map.y * 100.0),
+            // Center the dot on its position.
+            margin: UiRect::new(Val::Px(-size / 2.0), Val::ZERO, Val::Px(-size / 2.0), Val::ZERO),
+            width: Val::Px(size),
+            height: Val::Px(size),
+            border: UiRect::all(Val::Px(if border.is_some() { 2.0 } else { 0.0 })),
+            ..default()
+        };
+        let border_color = border.unwrap_or(Color::NONE);
+
+        match dots_by_target
+            .get(&target)
+            .and_then(|dot_entity| dots.get_mut(*dot_entity).ok())
+        {
+            Some((_, _, mut dot_style, mut background_color, mut dot_border_color)) => {
+                *dot_style = style;
+                background_color.0 = color;
+                dot_border_color.0 = border_color;
+            }
+            None => {
+                let dot = commands
+                    .spawn((
+                        Name::new("Minimap Dot"),
+                        MinimapDot(target),
+                        NodeBundle {
+                            style,
+                            background_color: color.into(),
+                            border_color: border_color.into(),
+                            border_radius: BorderRadius::MAX,
+                            // Let taps through to the map underneath.
+                            focus_policy: bevy::ui::FocusPolicy::Pass,
+                            ..default()
+                        },
+                    ))
+                    .id();
+                commands.entity(minimap).add_child(dot);
+            }
+        }
+    }
+
+    for mut style in &mut view_rects {
+        let view = interest_area.view;
+        let top_left = world_to_minimap(world_bounds.0, Vec2::new(view.min.x, view.max.y));
+        let bottom_right = world_to_minimap(world_bounds.0, Vec2::new(view.max.x, view.min.y));
+        style.left = Val::Percent(top_left.x * 100.0);
+        style.top = Val::Percent(top_left.y * 100.0);
+        style.width = Val::Percent((bottom_right.x - top_left.x) * 100.0);
+        style.height = Val::Percent((bottom_right.y - top_left.y) * 100.0);
+    }
+}
+
+fn spawn_ping(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    position: Vec2,
+    color: Color,
+) {
+    commands.spawn((
+        Name::new("Ping"),
+        Ping {
+            color,
+            timer: Timer::new(PING_DURATION, TimerMode::Once),
+        },
+        MaterialMesh2dBundle {
+            mesh: meshes
+                .add(Annulus::new(PING_RADIUS - 6.0, PING_RADIUS))
+                .into(),
+            material: materials.add(color),
+            transform: Transform::from_translation(position.extend(4.0)),
+            ..default()
+        },
+        StateScoped(Screen::Gameplay),
+    ));
+}
+
+/// Ping the spot under the cursor, or finger on mobile.
+fn ping_minimap(
+    trigger: Trigger<OnPress>,
+    mut commands: Commands,
+    minimaps: Query<&RelativeCursorPosition, With<Minimap>>,
+    players: Query<&Sprite, With<Player>>,
+    server_protocol: Res<ServerProtocol>,
+    mut rate_limiter: ResMut<OutboundRateLimiter>,
+    time: Res<Time>,
+    world_bounds: Res<WorldBounds>,
+    mut outbound_queues: Query<&mut OutboundQueue>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Some(normalized) = minimaps
+        .get(trigger.entity())
+        .ok()
+        .and_then(|cursor| cursor.normalized)
+    else {
+        return;
+    };
+    if !rate_limiter.try_acquire(C2SActionTypes::Ping, time.elapsed()) {
+        return;
+    }
+
+    let position = minimap_to_world(world_bounds.0, normalized.clamp(Vec2::ZERO, Vec2::ONE));
+    let color = players
+        .get_single()
+        .map_or(Color::WHITE, |sprite| sprite.color);
+    spawn_ping(&mut commands, &mut meshes, &mut materials, position, color);
+
+    if server_protocol.has_feature(ProtocolFeature::Ping) {
+        for mut queue in &mut outbound_queues {
+            queue.push(C2SActionTypes::Ping, build_ping_request_msg(position));
+        }
+    }
+}
+
+fn other_player_pinged_handler(
+    mut commands: Commands,
+    mut event_reader: EventReader<OtherPlayerPingedBevyEvent>,
+    registry: Res<PlayerRegistry>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for e in event_reader.read() {
+        let Some(player) = registry.get(&e.player_uuid) else {
+            info!("Ignoring a ping from unknown player {}", e.player_uuid);
+            continue;
+        };
+
+        spawn_ping(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            Vec2::new(e.x_position, e.y_position),
+            unpack_duck_color(player.color.clone()),
+        );
+    }
+}
+
+/// Pings grow and fade out, then disappear.
+fn update_pings(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pings: Query<(Entity, &mut Ping, &mut Transform)>,
+) {
+    for (entity, mut ping, mut transform) in &mut pings {
+        ping.timer.tick(time.delta());
+        if ping.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        transform.scale = Vec3::splat(0.5 + ping.timer.fraction());
+    }
+}
@@ -1,14 +1,27 @@
 use bevy::prelude::*;
 
+pub mod animation;
+pub mod auth;
+pub mod bumping;
+pub mod camera;
+pub mod collision;
+pub mod hud_layout;
+pub mod interest;
 pub mod level;
+pub mod minimap;
 mod movement;
 pub mod player;
-pub mod player_animation;
+pub mod rate_limit;
+pub mod player_registry;
+pub mod protocol;
+pub mod quack_voice;
 pub mod other_player;
-pub mod other_player_animation;
+pub mod outbound_queue;
 pub mod cracker;
+pub mod duck_animation;
 pub mod score;
 pub mod background;
+pub mod server_browser;
 pub mod websocket_connect;
 pub mod websocket_join_msg;
 pub mod websocket_move_msg;
@@ -18,15 +31,33 @@ pub mod check_silent_mode;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins((
+        animation::plugin,
         movement::plugin,
+        collision::plugin,
+        camera::plugin,
         level::plugin,
         player::plugin,
-        player_animation::plugin,
+        duck_animation::plugin,
         other_player::plugin,
-        other_player_animation::plugin,
+        player_registry::plugin,
+        hud_layout::plugin,
+        interest::plugin,
+        minimap::plugin,
+        bumping::plugin,
         cracker::plugin,
         score::plugin,
-        background::plugin,
+    ));
+
+    // Environment
+    app.add_plugins((background::plugin, quack_voice::plugin, check_silent_mode::plugin));
+
+    // Networking
+    app.add_plugins((
+        server_browser::plugin,
+        auth::plugin,
+        protocol::plugin,
+        rate_limit::plugin,
+        outbound_queue::plugin,
         websocket_connect::plugin,
         websocket_join_msg::plugin,
         websocket_move_msg::plugin,
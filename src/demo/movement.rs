@@ -7,7 +7,8 @@
 //!   This is done in the `player` module, as it is specific to the player
 //!   character.
 //! - Apply movement based on [`MovementController`] intent and maximum speed.
-//! - Wrap the character within the window.
+//! - Slide the character out of any level colliders it walked into.
+//! - Clamp the character to the level's [`WorldBounds`].
 //!
 //! Note that the implementation used here is limited for demonstration
 //! purposes. If you want to move the player in a smoother way,
@@ -17,16 +18,27 @@ use bevy::prelude::*;
 
 use crate::AppSet;
 
-use super::websocket_move_msg::MoveRequestEvent;
+use super::{
+    collision::{Collider, ObstacleGrid},
+    websocket_move_msg::MoveRequestEvent,
+};
 
-pub const MIN_X_POS: f32 = -1000.;
-pub const MIN_Y_POS: f32 = -1000.;
+/// The area ducks can walk around in. Set from the current level's bounds.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct WorldBounds(pub Rect);
 
-pub const MAX_X_POS: f32 = 1000.;
-pub const MAX_Y_POS: f32 = 1000.;
+/// How quickly knockback fades, per second.
+const KNOCKBACK_DECAY: f32 = 8.0;
+
+impl Default for WorldBounds {
+    fn default() -> Self {
+        Self(Rect::new(-1000., -1000., 1000., 1000.))
+    }
+}
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<MovementController>();
+    app.init_resource::<WorldBounds>();
 
     app.add_systems(Update, apply_movement.chain().in_set(AppSet::Update));
 }
@@ -44,6 +56,12 @@ pub struct MovementController {
     /// 1 world unit = 1 pixel when using the default 2D camera and no physics
     /// engine.
     pub max_speed: f32,
+
+    /// Scales `max_speed`, e.g. while wading through a hazard.
+    pub speed_multiplier: f32,
+
+    /// Extra velocity from being bumped around, fades out on its own.
+    pub knockback: Vec2,
 }
 
 impl Default for MovementController {
@@ -52,16 +70,17 @@ impl Default for MovementController {
             intent: Vec2::ZERO,
             // 400 pixels per second is a nice default, but we can still vary this per character.
             max_speed: 400.0,
+            speed_multiplier: 1.0,
+            knockback: Vec2::ZERO,
         }
     }
 }
 
-fn apply_movement(
+pub(super) fn apply_movement(
     time: Res<Time>,
-    mut param_set: ParamSet<(
-        Query<(&MovementController, &mut Transform)>,
-        Query<&mut Transform, With<Camera>>,
-    )>,
+    world_bounds: Res<WorldBounds>,
+    obstacle_grid: Res<ObstacleGrid>,
+    mut movement_query: Query<(&mut MovementController, &mut Transform, Option<&Collider>)>,
     mut move_request_event_writer: EventWriter<MoveRequestEvent>
 ) {
     let mut translation = Vec3 {
@@ -70,46 +89,49 @@ fn apply_movement(
         z: 0.,
     };
 
-    for (controller, mut transform) in &mut param_set.p0().iter_mut() {
-        let velocity = controller.max_speed * controller.intent;
+    for (mut controller, mut transform, collider) in &mut movement_query {
+        let start = transform.translation;
+        let velocity = controller.max_speed * controller.speed_multiplier * controller.intent
+            + controller.knockback;
+
+        if controller.knockback != Vec2::ZERO {
+            let knockback = controller.knockback * (-KNOCKBACK_DECAY * time.delta_seconds()).exp();
+            controller.knockback = if knockback.length() < 1.0 {
+                Vec2::ZERO
+            } else {
+                knockback
+            };
+        }
         transform.translation += velocity.extend(0.0) * time.delta_seconds();
-        translation = velocity.extend(0.0) * time.delta_seconds();
 
-        if transform.translation.x < MIN_X_POS {
-            transform.translation.x = MIN_X_POS
+        // Slide along any obstacles we walked into.
+        if let Some(collider) = collider {
+            let resolved = obstacle_grid.resolve(transform.translation.truncate(), *collider);
+            transform.translation = resolved.extend(transform.translation.z);
+        }
+
+        let bounds = world_bounds.0;
+        if transform.translation.x < bounds.min.x {
+            transform.translation.x = bounds.min.x
         }
-        if transform.translation.y < MIN_Y_POS {
-            transform.translation.y = MIN_Y_POS
+        if transform.translation.y < bounds.min.y {
+            transform.translation.y = bounds.min.y
         }
-        if transform.translation.x > MAX_X_POS {
-            transform.translation.x = MAX_X_POS
+        if transform.translation.x > bounds.max.x {
+            transform.translation.x = bounds.max.x
         }
-        if transform.translation.y > MAX_Y_POS {
-            transform.translation.y = MAX_Y_POS
+        if transform.translation.y > bounds.max.y {
+            transform.translation.y = bounds.max.y
         }
+
+        // Tell the server how far we actually got, not how far we tried to go.
+        translation = transform.translation - start;
     }
 
-    // No need to ping server and update camera if no change
-    if !(translation.x == 0. && translation.y == 0.) {   
-        for mut camera in &mut param_set.p1().iter_mut() {
-            camera.translation += translation;
-
-            // Keep camera movement within bounds
-            if camera.translation.x < MIN_X_POS {
-                camera.translation.x = MIN_X_POS
-            }
-            if camera.translation.y < MIN_Y_POS {
-                camera.translation.y = MIN_Y_POS
-            }
-            if camera.translation.x > MAX_X_POS {
-                camera.translation.x = MAX_X_POS
-            }
-            if camera.translation.y > MAX_Y_POS {
-                camera.translation.y = MAX_Y_POS
-            }
-            
-            // send movement request to ws server
-            move_request_event_writer.send(MoveRequestEvent(translation.x, translation.y));
-        }
+    // No need to ping server if no change.
+    // The camera follows on its own, see `camera.rs`.
+    if !(translation.x == 0. && translation.y == 0.) {
+        // send movement request to ws server
+        move_request_event_writer.send(MoveRequestEvent(translation.x, translation.y));
     }
 }
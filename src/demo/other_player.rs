@@ -1,17 +1,13 @@
 // "Other" player refers to all players that are not the one being controlled by the user
 
-use bevy::{
-    audio::{AudioPlugin, SpatialScale},
-    color::palettes::css::*,
-    prelude::*,
-    prelude::*,
-    render::texture::{ImageLoaderSettings, ImageSampler},
-    sprite::MaterialMesh2dBundle,
-};
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
 use serde::Deserialize;
 
 use super::{
-    player_animation::PlayerAnimationState,
+    animation::SpriteAnimator,
+    duck_animation::{DuckAssets, IDLE, WALK},
+    player_registry::{PlayerId, PlayerRegistry, RegisteredPlayer},
+    quack_voice::{QuackAssets, QuackVoice},
     websocket_connect::{
         OtherPlayerJoinedWsReceived, OtherPlayerMovedWsReceived, OtherPlayerQuackedWsReceived,
         S2CActionTypes, UserDisconnectedBevyEvent,
@@ -19,8 +15,7 @@ use super::{
 };
 
 use crate::{
-    asset_tracking::LoadResource,
-    demo::other_player_animation::{OtherPlayerAnimation, OtherPlayerAnimationState},
+    audio::AudioCommands,
     screens::Screen,
 };
 
@@ -37,8 +32,6 @@ use crate::{
 //     }
 // }
 
-use bevy_kira_audio::*;
-
 // #[derive(Debug, Deserialize)]
 // pub struct NewJoinerData {
 //     pub player_uuid: String,
@@ -148,11 +141,36 @@ pub struct QuackResponseData {
     pub player_x_position: f32,
     pub player_y_position: f32,
     pub quack_pitch: f32,
+    /// From servers that advertise the `quack_voice` feature.
+    #[serde(default)]
+    pub quack_voice: Option<String>,
 }
 
-// Tag for the listener (e.g., player or camera)
+const QUACK_INDICATOR_RADIUS: f32 = 15.0;
+const QUACK_INDICATOR_COLOR: Color = Color::srgba(0.0, 0.0, 1.0, 0.6);
+const QUACK_INDICATOR_SECS: f32 = 0.6;
+/// How much bigger the ring gets by the time it's faded out.
+const QUACK_INDICATOR_GROWTH: f32 = 2.0;
+
+/// A ring that shows another duck just quacked.
 #[derive(Component)]
-struct Listener;
+struct QuackIndicator {
+    /// The duck to stay on, if we've spawned it.
+    duck: Option<Entity>,
+    timer: Timer,
+}
+
+/// The circle every quack ring shares. Each ring gets its own material so it
+/// can fade on its own.
+#[derive(Resource)]
+struct QuackIndicatorMesh(Handle<Mesh>);
+
+impl FromWorld for QuackIndicatorMesh {
+    fn from_world(world: &mut World) -> Self {
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+        Self(meshes.add(Circle::new(QUACK_INDICATOR_RADIUS)))
+    }
+}
 
 // Tag for sound emitters
 // #[derive(Component)]
@@ -168,181 +186,73 @@ pub struct OtherMovedReceivedWsMsg {
 #[reflect(Component)]
 pub struct OtherPlayer;
 
-#[derive(Resource, Asset, Reflect, Clone)]
-pub struct OtherPlayerAssets {
-    #[dependency]
-    pub ducky: Handle<Image>,
-    #[dependency]
-    pub steps: Vec<Handle<bevy::audio::AudioSource>>,
-}
-
-impl OtherPlayerAssets {
-    pub const PATH_DUCKY: &'static str = "images/ducky.png";
-    pub const PATH_STEP_1: &'static str = "audio/sound_effects/step1.wav";
-    pub const PATH_STEP_2: &'static str = "audio/sound_effects/step2.wav";
-    pub const PATH_STEP_3: &'static str = "audio/sound_effects/step3.wav";
-    pub const PATH_STEP_4: &'static str = "audio/sound_effects/step4.wav";
-}
-
-impl FromWorld for OtherPlayerAssets {
-    fn from_world(world: &mut World) -> Self {
-        let assets = world.resource::<AssetServer>();
-        Self {
-            ducky: assets.load_with_settings(
-                OtherPlayerAssets::PATH_DUCKY,
-                |settings: &mut ImageLoaderSettings| {
-                    settings.sampler = ImageSampler::nearest();
-                },
-            ),
-            steps: vec![
-                assets.load(OtherPlayerAssets::PATH_STEP_1),
-                assets.load(OtherPlayerAssets::PATH_STEP_2),
-                assets.load(OtherPlayerAssets::PATH_STEP_3),
-                assets.load(OtherPlayerAssets::PATH_STEP_4),
-            ],
-        }
-    }
-}
-
-// #[derive(Resource)]
-// pub struct SpatialAudio {
-//     /// The volume will change from `1` at distance `0` to `0` at distance `max_distance`
-//     pub max_distance: f32,
-// }
-
-// impl SpatialAudio {
-//     pub(crate) fn update(
-//         &self,
-//         receiver_transform: &GlobalTransform,
-//         emitters: &Query<(&GlobalTransform, &AudioEmitter)>,
-//         audio_instances: &mut Assets<AudioInstance>,
-//     ) {
-//         for (emitter_transform, emitter) in emitters {
-//             let sound_path = emitter_transform.translation() - receiver_transform.translation();
-//             let volume = (1. - sound_path.length() / self.max_distance)
-//                 .clamp(0., 1.)
-//                 .powi(2);
-
-//             let right_ear_angle = receiver_transform.right().angle_between(sound_path);
-//             let panning = (right_ear_angle.cos() + 1.) / 2.;
-
-//             for instance in emitter.instances.iter() {
-//                 if let Some(instance) = audio_instances.get_mut(instance) {
-//                     instance.set_volume(volume as f64, AudioTween::default());
-//                     instance.set_panning(panning as f64, AudioTween::default());
-//                 }
-//             }
-//         }
-//     }
-// }
-
-// TODO - Still need to figure out proper spatial audio...
-// pub(crate) fn run_spatial_audio(
-//     spatial_audio: Res<SpatialAudio>,
-//     receiver: Query<&GlobalTransform, With<AudioReceiver>>,
-//     emitters: Query<(&GlobalTransform, &AudioEmitter)>,
-//     mut audio_instances: ResMut<Assets<AudioInstance>>,
-// ) {
-//     if let Ok(receiver_transform) = receiver.get_single() {
-//         spatial_audio.update(receiver_transform, &emitters, &mut audio_instances);
-//     }
-// }
-
-// pub(crate) fn cleanup_stopped_spatial_instances(
-//     mut emitters: Query<&mut AudioEmitter>,
-//     instances: ResMut<Assets<AudioInstance>>,
-// ) {
-//     for mut emitter in emitters.iter_mut() {
-//         let handles = &mut emitter.instances;
-
-//         handles.retain(|handle| {
-//             if let Some(_instance) = instances.get(handle) {
-//                 _instance.handle.state() != PlaybackState::Stopped
-//             } else {
-//                 true
-//             }
-//         });
-//     }
-// }
-
-/// Component for audio emitters
-///
-/// Add [`Handle<AudioInstance>`]s to control their pan and volume based on emitter
-/// and receiver positions.
-// #[derive(Component, Default)]
-// pub struct AudioEmitter {
-//     /// Audio instances that are played by this emitter
-
-//     /// The same instance should only be on one emitter.
-//     pub instances: Vec<Handle<AudioInstance>>,
-// }
-
-/// Component for the audio receiver
-///
-/// Most likely you will want to add this component to your player or you camera.
-/// The entity needs a [`Transform`] and [`GlobalTransform`]. The view direction of the [`GlobalTransform`]
-/// will
-// #[derive(Component)]
-// pub struct AudioReceiver;
-
-// #[derive(Bundle)]
-// struct SpatialAudioBundle {
-//     audio_source: Handle<bevy_kira_audio::AudioSource>,
-//     transform: Transform,
-//     global_transform: GlobalTransform,
-// }
-
-/// Spatial audio uses the distance to attenuate the sound volume. In 2D with the default camera,
-/// 1 pixel is 1 unit of distance, so we use a scale so that 100 pixels is 1 unit of distance for
-/// audio.
-
-// const AUDIO_SCALE: f32 = 1. / 100.0;
+/// The friendly name floating above another player's duck.
+#[derive(Component)]
+pub struct OtherPlayerNameLabel;
 
-#[derive(Component, Default)]
-pub struct Emitter {
-    stopped: bool,
-}
+/// Ducks that joined before [`DuckAssets`] finished loading, spawned once it
+/// has.
+#[derive(Resource, Default)]
+pub struct PendingOtherPlayerJoins(Vec<OtherPlayerJoinedWsReceived>);
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<OtherPlayer>();
-    app.load_resource::<OtherPlayerAssets>();
-    app.add_plugins(bevy_kira_audio::AudioPlugin);
-    // app.add_plugins(DefaultPlugins.set(AudioPlugin {
-    //     default_spatial_scale: SpatialScale::new_2d(AUDIO_SCALE),
-    //     ..default()
-    // }));
-    // app.insert_resource(SpatialAudio { max_distance: 25. });
-    // app.init_asset::<AudioSource>();
-    // app.insert_resource(SpatialScale { scale: 1.0 }); // Scale of spatial audio
+    app.init_resource::<PendingOtherPlayerJoins>();
+    app.init_resource::<QuackIndicatorMesh>();
+    // Joins from a server we've left shouldn't spawn ducks on the next one.
+    app.add_systems(OnExit(Screen::Gameplay), clear_pending_other_player_joins);
 
     app.add_systems(Update, other_player_joined_ws_msg_handler);
     app.add_systems(Update, other_player_moved_ws_msg_handler);
     app.add_systems(Update, other_player_quacked_handler);
+    app.add_systems(Update, animate_quack_indicators);
     app.add_systems(Update, other_player_disconnected_handler);
-
-    // app.add_systems(
-    //     Update,
-    //     run_spatial_audio.run_if(resource_exists::<SpatialAudio>),
-    // );
 }
 
-// app.add_plugins(DefaultPlugins.set(AudioPlugin {
-//     default_spatial_scale: SpatialScale::new_2d(AUDIO_SCALE),
-//     ..default()
-// }));
+fn clear_pending_other_player_joins(mut pending: ResMut<PendingOtherPlayerJoins>) {
+    pending.0.clear();
+}
 
 // spawn player
 pub fn other_player_joined_ws_msg_handler(
     mut event_reader: EventReader<OtherPlayerJoinedWsReceived>,
     mut commands: Commands,
-    player_assets_op: Option<Res<OtherPlayerAssets>>,
+    duck_assets_op: Option<Res<DuckAssets>>,
     asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut pending: ResMut<PendingOtherPlayerJoins>,
+    mut registry: ResMut<PlayerRegistry>,
+    mut other_players: Query<(&mut Transform, &mut Sprite, &Children), With<OtherPlayer>>,
+    mut name_labels: Query<&mut Text, With<OtherPlayerNameLabel>>,
 ) {
-    if let Some(player_assets) = player_assets_op {
-        for e in event_reader.read() {
+    pending.0.extend(event_reader.read().cloned());
+    if let Some(duck_assets) = duck_assets_op {
+        for e in pending.0.drain(..) {
             info!("other player joined!");
 
+            // Re-joining with the same uuid updates the duck we already have.
+            if let Some(entity) = registry
+                .entity(&e.data.player_uuid)
+                .filter(|entity| commands.get_entity(*entity).is_some())
+            {
+                info!("Player {} joined again, updating their duck", e.data.player_uuid);
+                update_other_player(
+                    &mut commands,
+                    entity,
+                    &e.data,
+                    &mut other_players,
+                    &mut name_labels,
+                );
+                registry.insert(
+                    e.data.player_uuid.clone(),
+                    RegisteredPlayer {
+                        entity,
+                        friendly_name: e.data.player_friendly_name.clone(),
+                        color: e.data.color.clone(),
+                    },
+                );
+                continue;
+            }
+
             // #[derive(Debug, Deserialize)]
             // pub struct NewJoinerData {
             //     pub player_uuid: String,
@@ -358,16 +268,12 @@ pub fn other_player_joined_ws_msg_handler(
                 "In other_player.rs handling the Other Player joined event {:?}!",
                 e
             );
-            let layout =
-                TextureAtlasLayout::from_grid(UVec2::splat(32), 6, 2, Some(UVec2::splat(1)), None);
-            let texture_atlas_layout = texture_atlas_layouts.add(layout);
-            let player_animation = OtherPlayerAnimation::new();
-
             let parent_entity = (
-                Name::new(e.data.player_uuid.clone()),
+                Name::new(format!("Other Player {}", e.data.player_friendly_name)),
                 OtherPlayer,
+                PlayerId(e.data.player_uuid.clone()),
                 SpriteBundle {
-                    texture: player_assets.ducky.clone(),
+                    texture: duck_assets.ducky.clone(),
                     transform: Transform {
                         scale: Vec3::new(
                             // 4.0 * if e.data.direction_facing
@@ -395,17 +301,14 @@ pub fn other_player_joined_ws_msg_handler(
                     },
                     ..Default::default()
                 },
-                TextureAtlas {
-                    layout: texture_atlas_layout.clone(),
-                    index: player_animation.get_atlas_index(),
-                },
-                player_animation,
+                SpriteAnimator::new(duck_assets.animations.clone(), IDLE)
+                    .with_atlas_offset(duck_assets.ducky_offset),
                 StateScoped(Screen::Gameplay),
             );
 
-            commands.spawn(parent_entity).with_children(|parent| {
+            let entity = commands.spawn(parent_entity).with_children(|parent| {
                 // Player name text that appears above the sprite
-                parent.spawn(Text2dBundle {
+                parent.spawn((OtherPlayerNameLabel, Text2dBundle {
                     text: Text::from_section(
                         e.data.player_friendly_name.clone(), // The text to display
                         TextStyle {
@@ -428,24 +331,68 @@ pub fn other_player_joined_ws_msg_handler(
                         ..Default::default()
                     },
                     ..Default::default()
-                });
-            });
+                }));
+            }).id();
+
+            registry.insert(
+                e.data.player_uuid.clone(),
+                RegisteredPlayer {
+                    entity,
+                    friendly_name: e.data.player_friendly_name.clone(),
+                    color: e.data.color.clone(),
+                },
+            );
         }
     }
 }
 
+/// Moves and recolors a duck that is already in the pond.
+fn update_other_player(
+    commands: &mut Commands,
+    entity: Entity,
+    data: &OtherPlayerData,
+    other_players: &mut Query<(&mut Transform, &mut Sprite, &Children), With<OtherPlayer>>,
+    name_labels: &mut Query<&mut Text, With<OtherPlayerNameLabel>>,
+) {
+    let color = unpack_duck_color(data.color.clone());
+    let flip_x = data.direction_facing == DuckDirection::Left;
+
+    let Ok((mut transform, mut sprite, children)) = other_players.get_mut(entity) else {
+        // Spawned earlier this frame, so it isn't queryable yet.
+        commands.entity(entity).insert((
+            Transform {
+                scale: Vec3::new(4.0, 4.0, 2.0),
+                translation: Vec3::new(data.x_position, data.y_position, 10.0),
+                ..Default::default()
+            },
+            Sprite {
+                color,
+                flip_x,
+                ..Default::default()
+            },
+        ));
+        return;
+    };
+
+    transform.translation.x = data.x_position;
+    transform.translation.y = data.y_position;
+    sprite.color = color;
+    sprite.flip_x = flip_x;
+
+    let mut labels = name_labels.iter_many_mut(children);
+    while let Some(mut label) = labels.fetch_next() {
+        label.sections[0].value = data.player_friendly_name.clone();
+    }
+}
+
 // spawn player
 pub fn other_player_moved_ws_msg_handler(
     mut event_reader: EventReader<OtherPlayerMovedWsReceived>,
-    mut other_players: Query<(
-        &OtherPlayer,
-        &mut Sprite,
-        &Name,
-        &mut Transform,
-        &mut OtherPlayerAnimation,
-    )>, // friendly_name: String,
-        // color: Color,
-        // max_speed: f32,
+    mut other_players: Query<
+        (&mut Sprite, &mut Transform, &mut SpriteAnimator),
+        With<OtherPlayer>,
+    >,
+    mut registry: ResMut<PlayerRegistry>,
 ) {
     for e in event_reader.read() {
         info!("Handling other player moved bevy event");
@@ -469,29 +416,34 @@ pub fn other_player_moved_ws_msg_handler(
             e
         );
 
-        for (_other_player, mut sprite, name, mut transform, mut animation) in
-            other_players.iter_mut()
-        {
-            info!("checking vs id map: {}", name.to_string());
-            if name.to_string() == other_player_moved_response_data.player_uuid {
-                println!(
-                    "Found entity with id: {}",
-                    other_player_moved_response_data.player_uuid
-                );
-
-                transform.translation.x = other_player_moved_response_data.new_x_position;
-                transform.translation.y = other_player_moved_response_data.new_y_position;
+        let Some(player) = registry.get_mut(&other_player_moved_response_data.player_uuid) else {
+            info!(
+                "No duck for player {}",
+                other_player_moved_response_data.player_uuid
+            );
+            continue;
+        };
+        let Ok((mut sprite, mut transform, mut animation)) = other_players.get_mut(player.entity)
+        else {
+            continue;
+        };
+
+        // The server may hand out a new color, eg. after a respawn.
+        if player.color != other_player_moved_response_data.color {
+            player.color = other_player_moved_response_data.color.clone();
+            sprite.color = unpack_duck_color(player.color.clone());
+        }
 
-                let dx = other_player_moved_response_data.new_x_position
-                    - other_player_moved_response_data.old_x_position;
+        transform.translation.x = other_player_moved_response_data.new_x_position;
+        transform.translation.y = other_player_moved_response_data.new_y_position;
 
-                sprite.flip_x = dx < 0.;
+        let dx = other_player_moved_response_data.new_x_position
+            - other_player_moved_response_data.old_x_position;
 
-                // animation.play_walking();
+        sprite.flip_x = dx < 0.;
 
-                animation.update_state(OtherPlayerAnimationState::Walking);
-            }
-        }
+        // Walk a cycle, then stand still unless they keep moving.
+        animation.play_loops(WALK, 1);
     }
 }
 
@@ -514,7 +466,8 @@ pub fn unpack_duck_color(color: String) -> Color {
 fn other_player_disconnected_handler(
     mut commands: Commands,
     mut event_reader: EventReader<UserDisconnectedBevyEvent>,
-    other_player_entities: Query<(Entity, &OtherPlayer, &Name)>,
+    mut pending: ResMut<PendingOtherPlayerJoins>,
+    mut registry: ResMut<PlayerRegistry>,
 ) {
     for e in event_reader.read() {
         let other_player_disconnected_data =
@@ -528,27 +481,34 @@ fn other_player_disconnected_handler(
                 }
             });
 
-        for (entity, _component, name) in other_player_entities.iter() {
-            if name.to_string() == other_player_disconnected_data.disconnected_player_uuid {
-                commands.entity(entity).despawn_recursive();
-                println!("Deleting duck for user: {}", name);
-            };
+        // Gone before their duck could be spawned.
+        pending.0.retain(|joined| {
+            joined.data.player_uuid != other_player_disconnected_data.disconnected_player_uuid
+        });
+
+        if let Some(player) =
+            registry.remove(&other_player_disconnected_data.disconnected_player_uuid)
+        {
+            if let Some(entity_commands) = commands.get_entity(player.entity) {
+                entity_commands.despawn_recursive();
+            }
+            println!("Deleting duck for user: {}", player.friendly_name);
         }
     }
 }
 
-// Just plays regular quack sound for now, without spatial audio
+/// Quacks from the other duck, in its own voice, and shows a ring around it
 fn other_player_quacked_handler(
     mut commands: Commands,
     mut event_reader: EventReader<OtherPlayerQuackedWsReceived>,
-    asset_server: Res<AssetServer>,
-    kira_audio: Res<Audio>,
-    mut meshes: ResMut<Assets<Mesh>>,
+    quack_assets: Option<Res<QuackAssets>>,
+    registry: Res<PlayerRegistry>,
+    indicator_mesh: Res<QuackIndicatorMesh>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
     for e in event_reader.read() {
-        let other_player_quacked_response_data = serde_json::from_value(e.data.clone())
-            .unwrap_or_else(|op| {
+        let other_player_quacked_response_data: QuackResponseData =
+            serde_json::from_value(e.data.clone()).unwrap_or_else(|op| {
                 info!("Failed to parse incoming websocket message: {}", op);
                 QuackResponseData {
                     player_uuid: "error".to_string(),
@@ -556,6 +516,7 @@ fn other_player_quacked_handler(
                     player_x_position: 0.,
                     player_y_position: 0.,
                     quack_pitch: 0.,
+                    quack_voice: None,
                 }
             });
 
@@ -564,84 +525,76 @@ fn other_player_quacked_handler(
             other_player_quacked_response_data
         );
 
-        // Get the position from the event data
-        let _quack_position = Vec3::new(
+        let quack_position = Vec2::new(
             other_player_quacked_response_data.player_x_position,
             other_player_quacked_response_data.player_y_position,
-            0.0, // Assuming 2D game, z-coordinate is 0
         );
+        // Ducks we haven't spawned yet still get heard, from where they quacked.
+        let duck = registry.entity(&other_player_quacked_response_data.player_uuid);
 
-        // let audio_handle = asset_server.load("audio/sound_effects/duck-quack.mp3");
-
-        // if let Some(audio_handle) = audio_assets.get(&quack_audio.sound_handle) {
-        // Spawn an audio source to play the sound
-        // commands.spawn()
-
-        // Nonspacial version
-        // commands.spawn(AudioSourceBundle {
-        //     source: quack_audio.sound_handle.clone(), // Clone the handle to use it
-
-        //     ..Default::default()                // Use default values for other fields
-        // });
-
-        // v1 - Non spatial
-        // commands
-        //     .spawn(TransformBundle::from_transform(Transform::from_xyz(
-        //         other_player_quacked_response_data.player_x_position,
-        //         other_player_quacked_response_data.player_y_position,
-        //         0.0,
-        //     ))) // Position emitter to the right
-        //     .insert(SoundEmitter);
-
-        // v2 - kira
-        // let audio_handle: Handle<bevy_kira_audio::AudioSource> = asset_server.load("audio/sound_effects/duck-quack.mp3");
-        // kira_audio.play(audio_handle);
-
-        // v3 - spatial w/ despawn
         commands.spawn((
+            Name::new("Quack indicator"),
             MaterialMesh2dBundle {
-                mesh: meshes.add(Circle::new(15.0)).into(),
-                material: materials.add(Color::from(BLUE)),
-                transform: Transform::from_translation(Vec3::new(
-                    other_player_quacked_response_data.player_x_position,
-                    other_player_quacked_response_data.player_y_position,
-                    100.0,
-                )),
+                mesh: indicator_mesh.0.clone().into(),
+                material: materials.add(QUACK_INDICATOR_COLOR),
+                transform: Transform::from_translation(quack_position.extend(100.0)),
                 ..default()
             },
-            Emitter::default(),
-            AudioBundle {
-                source: asset_server.load("audio/sound_effects/duck-quack.mp3"),
-                settings: PlaybackSettings::DESPAWN.with_spatial(true),
+            QuackIndicator {
+                duck,
+                timer: Timer::from_seconds(QUACK_INDICATOR_SECS, TimerMode::Once),
             },
+            StateScoped(Screen::Gameplay),
         ));
 
-       
-        // // Play the audio at the position defined above
-        // audio.play(audio_handle.clone());
-
-        //     audio_handle,
-        //     Transform {
-        //         translation: Vec3::new(other_player_quacked_response_data.player_x_position,
-        //             other_player_quacked_response_data.player_y_position, 0.0), // Position the audio in 3D space
-        //         ..Default::default()
-        //     },
-        //     GlobalTransform::default(), // Required for spatial positioning
-        // ));
-
-        // commands.spawn((
-        //     SpatialAudio {
-        //         emitter_position: quack_position, // Position of the quacking player
-        //         listener_position: listener_transform.translation, // Player's current position (listener)
-        //         ..Default::default() // Other default values, such as attenuation and falloff
-        //     },
-        //     audio.sound_handle.clone(), // Play the quack sound
-        //     PlaybackSettings::ONCE.with_pitch(other_player_quacked_response_data.quack_pitch), // Adjust the pitch dynamically
-        // ));
-
-        //     println!("Received quack message! Playing sound.");
-        // } else {
-        //     println!("Audio not loaded yet.");
-        // }
+        let Some(quack_assets) = quack_assets.as_ref() else {
+            warn!("Quack sounds aren't loaded, another duck quacked silently");
+            continue;
+        };
+        let voice = other_player_quacked_response_data
+            .quack_voice
+            .as_deref()
+            .and_then(QuackVoice::from_id)
+            .unwrap_or_else(|| {
+                QuackVoice::for_player(&other_player_quacked_response_data.player_uuid)
+            });
+        let quack = quack_assets.quack(voice, other_player_quacked_response_data.quack_pitch);
+        match duck {
+            Some(duck) => commands.play_sound_effect_on(duck, quack),
+            None => commands.play_sound_effect_at(quack_position, quack),
+        };
+    }
+}
+
+/// Grow and fade the ring around a quacking duck, keeping it on the duck,
+/// and clear it away once it's gone.
+fn animate_quack_indicators(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut indicators: Query<(
+        Entity,
+        &mut QuackIndicator,
+        &mut Transform,
+        &Handle<ColorMaterial>,
+    )>,
+    ducks: Query<&Transform, (With<OtherPlayer>, Without<QuackIndicator>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (entity, mut indicator, mut transform, material) in &mut indicators {
+        indicator.timer.tick(time.delta());
+        if indicator.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        if let Some(duck_transform) = indicator.duck.and_then(|duck| ducks.get(duck).ok()) {
+            transform.translation.x = duck_transform.translation.x;
+            transform.translation.y = duck_transform.translation.y;
+        }
+        let progress = indicator.timer.fraction();
+        transform.scale = Vec3::splat(1.0 + progress * QUACK_INDICATOR_GROWTH);
+        if let Some(material) = materials.get_mut(material) {
+            material.color = QUACK_INDICATOR_COLOR.with_alpha(QUACK_INDICATOR_COLOR.alpha() * (1.0 - progress));
+        }
     }
 }
@@ -0,0 +1,187 @@
+//! Buffers outbound websocket messages until the socket can take them.
+//!
+//! Messages are pushed onto a client's [`OutboundQueue`] instead of being sent
+//! straight away. The queue is spawned with the client entity as soon as we
+//! start connecting, and is flushed in order once the socket is open and the
+//! join request has been queued.
+//!
+//! There is no reconnect yet, so buffering across a reconnect is out of scope.
+//! If the socket drops, failed sends stay at the front of the queue and the
+//! queue is dropped with the client entity when we leave gameplay.
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use super::{
+    websocket_connect::{C2SActionTypes, WebSocketClient},
+    websocket_join_msg::JoinPending,
+    websocket_move_msg::build_move_request_msg,
+};
+
+/// The most messages we hold on to while the socket isn't open.
+const MAX_QUEUED_MESSAGES: usize = 64;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<OutboundSendFailed>();
+    app.add_systems(Update, (flush_outbound_queue, log_send_failures).chain());
+}
+
+#[derive(Component, Debug, Default)]
+pub struct OutboundQueue {
+    messages: VecDeque<QueuedMessage>,
+}
+
+#[derive(Debug, Clone)]
+enum QueuedMessage {
+    /// Kept as a delta rather than text so back to back moves can be added up.
+    Move(Vec2),
+    Text {
+        action: C2SActionTypes,
+        text: String,
+    },
+}
+
+impl QueuedMessage {
+    fn action(&self) -> C2SActionTypes {
+        match self {
+            QueuedMessage::Move(_) => C2SActionTypes::Move,
+            QueuedMessage::Text { action, .. } => *action,
+        }
+    }
+
+    fn to_text(&self) -> String {
+        match self {
+            QueuedMessage::Move(delta) => build_move_request_msg(delta.x, delta.y),
+            QueuedMessage::Text { text, .. } => text.clone(),
+        }
+    }
+}
+
+impl OutboundQueue {
+    pub fn push(&mut self, action: C2SActionTypes, text: String) {
+        self.push_message(QueuedMessage::Text { action, text });
+    }
+
+    /// Queues a message ahead of everything else, e.g. the join request.
+    pub fn push_front(&mut self, action: C2SActionTypes, text: String) {
+        self.messages
+            .push_front(QueuedMessage::Text { action, text });
+    }
+
+    /// Queues a move, adding it onto the last queued message if that is a move too.
+    pub fn push_move(&mut self, delta: Vec2) {
+        if let Some(QueuedMessage::Move(last_delta)) = self.messages.back_mut() {
+            *last_delta += delta;
+            return;
+        }
+        self.push_message(QueuedMessage::Move(delta));
+    }
+
+    fn push_message(&mut self, message: QueuedMessage) {
+        if self.messages.len() >= MAX_QUEUED_MESSAGES {
+            // Moves go stale the fastest, so drop those first.
+            let index = self
+                .messages
+                .iter()
+                .position(|message| matches!(message, QueuedMessage::Move(_)))
+                .unwrap_or(0);
+            if let Some(dropped) = self.messages.remove(index) {
+                warn!(
+                    "Outbound queue is full, dropping a {:?} message",
+                    dropped.action()
+                );
+            }
+        }
+        self.messages.push_back(message);
+    }
+}
+
+/// A queued message that the socket refused.
+#[derive(Event, Debug, Clone)]
+pub struct OutboundSendFailed {
+    pub action: C2SActionTypes,
+    pub error: String,
+}
+
+fn flush_outbound_queue(
+    mut clients: Query<(&mut WebSocketClient, &mut OutboundQueue), Without<JoinPending>>,
+    mut send_failed_events: EventWriter<OutboundSendFailed>,
+) {
+    for (mut client, mut queue) in &mut clients {
+        if !client.is_open() {
+            continue;
+        }
+
+        while let Some(message) = queue.messages.front() {
+            if let Err(error) = client.send_text(message.to_text()) {
+                send_failed_events.send(OutboundSendFailed {
+                    action: message.action(),
+                    error,
+                });
+                // Keep it queued and retry next frame rather than losing it.
+                break;
+            }
+            queue.messages.pop_front();
+        }
+    }
+}
+
+fn log_send_failures(mut send_failed_events: EventReader<OutboundSendFailed>) {
+    for e in send_failed_events.read() {
+        warn!("Could not send the {:?} message: {}", e.action, e.error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves(queue: &OutboundQueue) -> Vec<Option<Vec2>> {
+        queue
+            .messages
+            .iter()
+            .map(|message| match message {
+                QueuedMessage::Move(delta) => Some(*delta),
+                QueuedMessage::Text { .. } => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn back_to_back_moves_are_added_up() {
+        let mut queue = OutboundQueue::default();
+        queue.push_move(Vec2::new(1.0, 0.0));
+        queue.push_move(Vec2::new(0.0, 2.0));
+        queue.push_move(Vec2::new(-3.0, 1.0));
+        assert_eq!(moves(&queue), vec![Some(Vec2::new(-2.0, 3.0))]);
+    }
+
+    #[test]
+    fn moves_are_not_added_across_other_messages() {
+        let mut queue = OutboundQueue::default();
+        queue.push_move(Vec2::X);
+        queue.push(C2SActionTypes::Quack, "quack".to_string());
+        queue.push_move(Vec2::Y);
+        queue.push_move(Vec2::Y);
+        assert_eq!(
+            moves(&queue),
+            vec![Some(Vec2::X), None, Some(Vec2::new(0.0, 2.0))]
+        );
+    }
+
+    #[test]
+    fn a_full_queue_drops_the_oldest_move_first() {
+        let mut queue = OutboundQueue::default();
+        queue.push(C2SActionTypes::Quack, "quack".to_string());
+        queue.push_move(Vec2::X);
+        while queue.messages.len() < MAX_QUEUED_MESSAGES {
+            queue.push(C2SActionTypes::Interact, "interact".to_string());
+        }
+        queue.push(C2SActionTypes::Ping, "ping".to_string());
+
+        assert_eq!(queue.messages.len(), MAX_QUEUED_MESSAGES);
+        assert_eq!(queue.messages[0].action(), C2SActionTypes::Quack);
+        assert!(moves(&queue).iter().all(Option::is_none));
+    }
+}
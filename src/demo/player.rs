@@ -1,47 +1,60 @@
 use bevy::color::palettes::css::{LIME, RED};
 use bevy::prelude::*;
-use bevy::render::texture::{ImageLoaderSettings, ImageSampler};
-use bevy_kira_audio::Audio;
 use virtual_joystick::{
     create_joystick, JoystickFloating, JoystickInvisible, NoAction, VirtualJoystickEvent,
-    VirtualJoystickPlugin,
+    VirtualJoystickPlugin, VirtualJoystickUIBackground, VirtualJoystickUIKnob,
 };
 // use bevy::ui::{UiPlugin};
 // use bevy::prelude::*;
 
 use crate::theme::palette::{BUTTON_TEXT, NODE_BACKGROUND};
-use bevy_kira_audio::AudioControl;
 
 use crate::demo::other_player::{unpack_duck_color, NewJoinerDataWithAllPlayers};
 use crate::{
+    asset_manifest::{ui_atlas, AssetManifest},
     asset_tracking::LoadResource,
-    demo::{movement::MovementController, player_animation::PlayerAnimation},
+    audio::AudioCommands,
+    demo::{
+        animation::SpriteAnimator,
+        duck_animation::{DuckAssets, IDLE},
+        movement::MovementController,
+    },
     screens::Screen,
 };
 
 use super::websocket_connect::{
     MoveCrackersBevyEvent, OtherPlayerJoinedWsReceived, YouJoinedWsReceived,
 };
+use super::collision::Collider;
+use super::quack_voice::{PlayerProfile, QuackAssets};
+use super::hud_layout::ResponsiveHud;
+use super::rate_limit::OutboundRateLimiter;
+use super::websocket_connect::C2SActionTypes;
 use super::websocket_quack_msg::QuackRequestEvent;
 
-#[derive(Resource)]
-pub struct QuackAudio {
-    pub sound_handle: Handle<AudioSource>,
-}
-
 pub(super) fn plugin(app: &mut App) {
-    app.load_resource::<PlayerAssets>();
     app.register_type::<Player>();
 
     app.add_plugins(VirtualJoystickPlugin::<String>::default());
     // app.add_plugins(ShapePlugin);
-    app.add_systems(Startup, create_joystick_scene);
+    app.load_resource::<JoystickAssets>();
+    app.add_systems(
+        Update,
+        (
+            create_joystick_scene.run_if(resource_added::<JoystickAssets>),
+            crop_packed_joystick_images.run_if(resource_exists::<JoystickAssets>),
+        )
+            .chain(),
+    );
     app.add_systems(Update, handle_joystick_or_keyboard_input);
-    app.add_systems(Startup, quack_sound_setup);
     app.add_systems(Startup, add_quack_button);
     app.add_systems(Update, spacebar_quack_system);
+    app.init_resource::<PendingYouJoined>();
     app.add_systems(Update, you_joined_ws_msg_handler);
+    // A join from a server we've left shouldn't spawn our duck on the next one.
+    app.add_systems(OnExit(Screen::Gameplay), clear_pending_you_joined);
     app.add_systems(Update, quack_btn_handler);
+    app.add_systems(Update, update_quack_cooldown_ring);
     app.add_systems(Startup, spacial_listener_setup);
 }
 
@@ -56,15 +69,22 @@ fn spacial_listener_setup(
 fn quack_btn_handler(
     commands: Commands,
     interaction_query: Query<(Entity, &Interaction, &QuackBtnButton), Changed<Interaction>>,
-    quack_audio: Res<QuackAudio>,
-    audio_assets: Res<Assets<AudioSource>>,
+    quack_assets: Option<Res<QuackAssets>>,
+    profile: Res<PlayerProfile>,
     mut quack_request_bevy_event_writer: EventWriter<QuackRequestEvent>,
+    rate_limiter: Res<OutboundRateLimiter>,
+    time: Res<Time>,
 ) {
     for (_entity, interaction, _quack_btn) in &interaction_query {
         if matches!(interaction, Interaction::Pressed) {
-            println!("clicked quack btn!");
+            debug!("Quack button clicked.");
 
-            play_sound_nonspatial(commands, audio_assets, quack_audio);
+            // The cooldown ring on the button shows why nothing happened.
+            if !rate_limiter.is_ready(C2SActionTypes::Quack, time.elapsed()) {
+                return;
+            }
+
+            play_sound_nonspatial(commands, quack_assets, profile);
             quack_request_bevy_event_writer.send(QuackRequestEvent);
             return;
         }
@@ -109,13 +129,11 @@ fn add_quack_button(mut commands: Commands) {
             ButtonBundle {
                 // name: Name::new("QuackButton"),
                 style: Style {
-                    width: Val::Px(65.0),
-                    height: Val::Px(65.0),
+                    width: Val::Px(QUACK_BUTTON_SIZE),
+                    height: Val::Px(QUACK_BUTTON_SIZE),
                     justify_content: JustifyContent::Center,
                     align_items: AlignItems::Center,
                     position_type: PositionType::Absolute, // locks button to the "HUD"
-                    right: Val::Percent(5.0),
-                    bottom: Val::Percent(5.0),
                     ..Default::default()
                 },
                 // background_color: Color::srgb(0.15, 0.15, 0.15).into(),
@@ -124,6 +142,11 @@ fn add_quack_button(mut commands: Commands) {
             },
         )
         .insert(QuackBtnButton)
+        // Higher up in landscape, where a thumb rests on the side of the phone.
+        .insert(ResponsiveHud::bottom_right(
+            (Val::Percent(5.0), Val::Percent(5.0)),
+            (Val::Percent(6.0), Val::Percent(20.0)),
+        ))
         .with_children(|children| {
             children.spawn((
                 Name::new("QuackButton Text"),
@@ -136,6 +159,32 @@ fn add_quack_button(mut commands: Commands) {
                     },
                 ),
             ));
+
+            // A ring of dots around the button that empties as the quack cooldown runs out
+            for index in 0..QUACK_COOLDOWN_RING_DOTS {
+                let angle = index as f32 / QUACK_COOLDOWN_RING_DOTS as f32 * std::f32::consts::TAU;
+                let center = QUACK_BUTTON_SIZE / 2.0;
+                let radius = QUACK_BUTTON_SIZE / 2.0 + 10.0;
+                let dot_size = 6.0;
+                children.spawn((
+                    Name::new("QuackButton Cooldown Dot"),
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            width: Val::Px(dot_size),
+                            height: Val::Px(dot_size),
+                            left: Val::Px(center + radius * angle.sin() - dot_size / 2.0),
+                            top: Val::Px(center - radius * angle.cos() - dot_size / 2.0),
+                            ..default()
+                        },
+                        background_color: BUTTON_TEXT.into(),
+                        border_radius: BorderRadius::MAX,
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                    QuackCooldownDot(index),
+                ));
+            }
         });
 }
 
@@ -143,66 +192,103 @@ fn add_quack_button(mut commands: Commands) {
 #[derive(Component)]
 struct QuackBtnButton;
 
-fn quack_sound_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let sound_handle = asset_server.load("audio/sound_effects/duck-quack.mp3");
+const QUACK_BUTTON_SIZE: f32 = 65.0;
+const QUACK_COOLDOWN_RING_DOTS: usize = 16;
+
+/// One of the dots in the ring around the [`QuackBtnButton`], going clockwise from the top.
+#[derive(Component)]
+struct QuackCooldownDot(usize);
 
-    // Store the handle in a resource
-    commands.insert_resource(QuackAudio { sound_handle });
+fn update_quack_cooldown_ring(
+    rate_limiter: Res<OutboundRateLimiter>,
+    time: Res<Time>,
+    mut dots: Query<(&QuackCooldownDot, &mut Visibility)>,
+) {
+    let remaining = rate_limiter.cooldown_fraction(C2SActionTypes::Quack, time.elapsed());
+    for (dot, mut visibility) in &mut dots {
+        let dot_position = (dot.0 as f32 + 0.5) / QUACK_COOLDOWN_RING_DOTS as f32;
+        let new_visibility = if dot_position < remaining {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != new_visibility {
+            *visibility = new_visibility;
+        }
+    }
 }
 
 fn spacebar_quack_system(
     mut commands: Commands,
-    quack_audio: Res<QuackAudio>,
-    kira_audio: Res<Audio>,
-    asset_server: Res<AssetServer>,
+    quack_assets: Option<Res<QuackAssets>>,
+    profile: Res<PlayerProfile>,
     keyboard_input: Res<ButtonInput<KeyCode>>, // Input resource for key events
-    audio_assets: Res<Assets<AudioSource>>,    // Query to find entities to affect
     mut quack_request_bevy_event_writer: EventWriter<QuackRequestEvent>,
+    rate_limiter: Res<OutboundRateLimiter>,
+    time: Res<Time>,
 ) {
     if keyboard_input.just_pressed(KeyCode::Space) {
-        println!("Space pressed!");
-
-        if let Some(quack_audio_source) = audio_assets.get(&quack_audio.sound_handle) {
-            // Spawn an audio source to play the sound
-            play_sound_nonspatial(commands, audio_assets, quack_audio);
-
-            // let audio_handle: Handle<bevy_kira_audio::AudioSource> = asset_server.load("audio/sound_effects/duck-quack.mp3");
-            // kira_audio.play(audio_handle);
+        debug!("Space pressed.");
 
-            println!("Playing your quack sound.");
-
-            quack_request_bevy_event_writer.send(QuackRequestEvent);
-        } else {
-            println!("Audio not loaded yet.");
+        if !rate_limiter.is_ready(C2SActionTypes::Quack, time.elapsed()) {
+            debug!("Quack is cooling down.");
+            return;
         }
+
+        play_sound_nonspatial(commands, quack_assets, profile);
+        quack_request_bevy_event_writer.send(QuackRequestEvent);
     }
 }
 
-/// Plays NON-spatial sounds from your duck
+/// Plays NON-spatial sounds from your duck, in the voice from your profile
 fn play_sound_nonspatial(
     mut commands: Commands,
-    audio_assets: Res<Assets<AudioSource>>,
-    quack_audio: Res<QuackAudio>,
+    quack_assets: Option<Res<QuackAssets>>,
+    profile: Res<PlayerProfile>,
 ) {
-    if let Some(_) = audio_assets.get(&quack_audio.sound_handle) {
-        println!("Playing your quack sound.");
+    if let Some(quack_assets) = quack_assets {
+        debug!("Playing your quack sound.");
 
-        commands.spawn(AudioSourceBundle {
-            source: quack_audio.sound_handle.clone(),
-            settings: PlaybackSettings::DESPAWN,
-            ..Default::default()
-        });
+        commands.play_sound_effect(quack_assets.quack(profile.quack_voice, 0.0));
     } else {
-        println!("Audio not loaded yet.");
+        // The loading screen waits for them, so this shouldn't happen.
+        warn!("Quack sounds aren't loaded, quacking silently");
     }
 }
 
-fn create_joystick_scene(mut cmd: Commands, asset_server: Res<AssetServer>) {
+#[derive(Resource, Asset, Reflect, Clone)]
+pub struct JoystickAssets {
+    #[dependency]
+    knob: Handle<Image>,
+    /// Where the knob is in `knob`, if it was packed into an atlas.
+    knob_rect: Option<URect>,
+    #[dependency]
+    outline: Handle<Image>,
+    /// Where the outline is in `outline`, if it was packed into an atlas.
+    outline_rect: Option<URect>,
+}
+
+impl FromWorld for JoystickAssets {
+    fn from_world(world: &mut World) -> Self {
+        let manifest = world.resource::<AssetManifest>();
+        let assets = world.resource::<AssetServer>();
+        let knob = manifest.image("joystick_knob");
+        let outline = manifest.image("joystick_outline");
+        Self {
+            knob: knob.load(assets),
+            knob_rect: knob.rect,
+            outline: outline.load(assets),
+            outline_rect: outline.rect,
+        }
+    }
+}
+
+fn create_joystick_scene(mut cmd: Commands, joystick_assets: Res<JoystickAssets>) {
     create_joystick(
         &mut cmd,
         "UniqueJoystick".to_string(),
-        asset_server.load("Knob.png"),
-        asset_server.load("Outline.png"),
+        joystick_assets.knob.clone(),
+        joystick_assets.outline.clone(),
         None,
         None,
         None,
@@ -221,22 +307,62 @@ fn create_joystick_scene(mut cmd: Commands, asset_server: Res<AssetServer>) {
     );
 }
 
+/// `create_joystick` shows the whole image, which is the whole atlas if the
+/// knob and outline were packed into one.
+fn crop_packed_joystick_images(
+    mut commands: Commands,
+    joystick_assets: Res<JoystickAssets>,
+    images: Res<Assets<Image>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    parts: Query<
+        (Entity, Has<VirtualJoystickUIKnob>),
+        Or<(Added<VirtualJoystickUIKnob>, Added<VirtualJoystickUIBackground>)>,
+    >,
+) {
+    for (entity, is_knob) in &parts {
+        let (image, rect) = if is_knob {
+            (&joystick_assets.knob, joystick_assets.knob_rect)
+        } else {
+            (&joystick_assets.outline, joystick_assets.outline_rect)
+        };
+        let Some(rect) = rect else {
+            continue;
+        };
+        if let Some(atlas) = ui_atlas(image, rect, &images, &mut layouts) {
+            commands.entity(entity).insert(atlas);
+        }
+    }
+}
+
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
 #[reflect(Component)]
 pub struct Player;
 
+/// Roughly the duck's body, without the empty space around it in the sprite.
+pub const DUCK_COLLIDER_RADIUS: f32 = 24.0;
+
 // spawn player
+/// Our join, if it came in before [`DuckAssets`] finished loading, handled
+/// once it has.
+#[derive(Resource, Default)]
+pub struct PendingYouJoined(Vec<YouJoinedWsReceived>);
+
+fn clear_pending_you_joined(mut pending: ResMut<PendingYouJoined>) {
+    pending.0.clear();
+}
+
 pub fn you_joined_ws_msg_handler(
     mut event_reader: EventReader<YouJoinedWsReceived>,
     mut bevy_move_crackers_event_writer: EventWriter<MoveCrackersBevyEvent>,
     mut commands: Commands,
-    player_assets_op: Option<Res<PlayerAssets>>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    duck_assets_op: Option<Res<DuckAssets>>,
     asset_server: Res<AssetServer>,
     mut bevy_event_writer_other_player_joined: EventWriter<OtherPlayerJoinedWsReceived>,
+    mut pending: ResMut<PendingYouJoined>,
 ) {
-    if let Some(player_assets) = player_assets_op {
-        for e in event_reader.read() {
+    pending.0.extend(event_reader.read().cloned());
+    if let Some(duck_assets) = duck_assets_op {
+        for e in pending.0.drain(..) {
             let you_joined_response_data =
                 serde_json::from_value(e.data.clone()).unwrap_or_else(|op| {
                     info!("Failed to parse incoming websocket message: {}", op);
@@ -269,18 +395,13 @@ pub fn you_joined_ws_msg_handler(
             });
 
             info!("In player.rs handling the You joined event {:?}!", e);
-            let layout =
-                TextureAtlasLayout::from_grid(UVec2::splat(32), 6, 2, Some(UVec2::splat(1)), None);
-            let texture_atlas_layout = texture_atlas_layouts.add(layout);
-            let player_animation = PlayerAnimation::new();
-
             // Spawn your duck!
             commands
                 .spawn((
                     Name::new("foo".to_string()),
                     Player,
                     SpriteBundle {
-                        texture: player_assets.ducky.clone(),
+                        texture: duck_assets.ducky.clone(),
                         transform: Transform {
                             scale: Vec2::splat(4.0).extend(2.0),
                             translation: Vec3::new(
@@ -296,15 +417,15 @@ pub fn you_joined_ws_msg_handler(
                         },
                         ..Default::default()
                     },
-                    TextureAtlas {
-                        layout: texture_atlas_layout.clone(),
-                        index: player_animation.get_atlas_index(),
-                    },
+                    SpriteAnimator::new(duck_assets.animations.clone(), IDLE)
+                    .with_atlas_offset(duck_assets.ducky_offset),
                     MovementController {
                         max_speed: 500.,
                         ..default()
                     },
-                    player_animation,
+                    Collider::Circle {
+                        radius: DUCK_COLLIDER_RADIUS,
+                    },
                     StateScoped(Screen::Gameplay),
                 ))
                 .with_children(|parent| {
@@ -329,7 +450,7 @@ pub fn you_joined_ws_msg_handler(
                 .with_children(|parent| {
                     let gap = 40.0;
 
-                    println!("Adding spatial listener!");
+                    debug!("Adding the spatial listener.");
 
                     let listener = SpatialListener::new(gap);
                     parent
@@ -409,39 +530,3 @@ fn handle_joystick_or_keyboard_input(
         controller.intent = intent;
     }
 }
-
-#[derive(Resource, Asset, Reflect, Clone)]
-pub struct PlayerAssets {
-    #[dependency]
-    pub ducky: Handle<Image>,
-    #[dependency]
-    pub steps: Vec<Handle<AudioSource>>,
-}
-
-impl PlayerAssets {
-    pub const PATH_DUCKY: &'static str = "images/ducky.png";
-    pub const PATH_STEP_1: &'static str = "audio/sound_effects/step1.wav";
-    pub const PATH_STEP_2: &'static str = "audio/sound_effects/step2.wav";
-    pub const PATH_STEP_3: &'static str = "audio/sound_effects/step3.wav";
-    pub const PATH_STEP_4: &'static str = "audio/sound_effects/step4.wav";
-}
-
-impl FromWorld for PlayerAssets {
-    fn from_world(world: &mut World) -> Self {
-        let assets = world.resource::<AssetServer>();
-        Self {
-            ducky: assets.load_with_settings(
-                PlayerAssets::PATH_DUCKY,
-                |settings: &mut ImageLoaderSettings| {
-                    settings.sampler = ImageSampler::nearest();
-                },
-            ),
-            steps: vec![
-                assets.load(PlayerAssets::PATH_STEP_1),
-                assets.load(PlayerAssets::PATH_STEP_2),
-                assets.load(PlayerAssets::PATH_STEP_3),
-                assets.load(PlayerAssets::PATH_STEP_4),
-            ],
-        }
-    }
-}
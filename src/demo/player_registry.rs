@@ -0,0 +1,67 @@
+//! Keeps track of which entity belongs to which other player, so websocket
+//! handlers can look a duck up by its uuid instead of scanning every duck.
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::screens::Screen;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<PlayerId>();
+    app.init_resource::<PlayerRegistry>();
+
+    // The ducks are state scoped, so their entries go with them.
+    app.add_systems(OnExit(Screen::Gameplay), clear_player_registry);
+}
+
+/// The server's uuid for the player this duck belongs to.
+#[derive(Component, Debug, Clone, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Component)]
+pub struct PlayerId(pub String);
+
+#[derive(Debug, Clone)]
+pub struct RegisteredPlayer {
+    pub entity: Entity,
+    pub friendly_name: String,
+    pub color: String,
+}
+
+/// Every other player currently in the pond, by uuid.
+#[derive(Resource, Debug, Default)]
+pub struct PlayerRegistry {
+    players: HashMap<String, RegisteredPlayer>,
+}
+
+impl PlayerRegistry {
+    pub fn get(&self, player_uuid: &str) -> Option<&RegisteredPlayer> {
+        self.players.get(player_uuid)
+    }
+
+    pub fn get_mut(&mut self, player_uuid: &str) -> Option<&mut RegisteredPlayer> {
+        self.players.get_mut(player_uuid)
+    }
+
+    pub fn entity(&self, player_uuid: &str) -> Option<Entity> {
+        self.get(player_uuid).map(|player| player.entity)
+    }
+
+    /// Friendly names aren't unique, so this is the first match.
+    pub fn entity_by_friendly_name(&self, friendly_name: &str) -> Option<Entity> {
+        self.players
+            .values()
+            .find(|player| player.friendly_name == friendly_name)
+            .map(|player| player.entity)
+    }
+
+    /// Adds a player, replacing any earlier entry for the same uuid.
+    pub fn insert(&mut self, player_uuid: String, player: RegisteredPlayer) {
+        self.players.insert(player_uuid, player);
+    }
+
+    pub fn remove(&mut self, player_uuid: &str) -> Option<RegisteredPlayer> {
+        self.players.remove(player_uuid)
+    }
+}
+
+fn clear_player_registry(mut registry: ResMut<PlayerRegistry>) {
+    registry.players.clear();
+}
@@ -0,0 +1,132 @@
+//! Versioning for the websocket protocol shared with the backend.
+//!
+//! We send our [`PROTOCOL_VERSION`] in the join request, and the server greets
+//! every new connection with a `hello` listing the versions it speaks and the
+//! optional features it supports. If it can't speak our version we bail out to
+//! the [`Screen::OutOfDate`] screen instead of failing to parse everything it
+//! sends us. Newer message types should only be sent once the server has
+//! advertised the matching [`ProtocolFeature`].
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::screens::Screen;
+
+use super::websocket_connect::WebSocketConnectionEvents;
+
+/// The version of the message schema this client speaks. Bump it whenever a
+/// message changes in a way older servers can't handle.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// How long to wait for the server's hello before assuming it is an older
+/// server that doesn't send one.
+pub const HELLO_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ServerProtocol>();
+    app.add_event::<ServerHelloBevyEvent>();
+
+    app.add_systems(Update, (forget_server_protocol, server_hello_handler).chain());
+}
+
+/// Optional parts of the protocol a server can advertise in its hello.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtocolFeature {
+    /// Answers `server_info` requests with its player count.
+    ServerInfo,
+    /// Checks the auth token sent with the join request.
+    AuthToken,
+    /// Takes `view_rect` messages and sends fewer updates about ducks outside it.
+    InterestArea,
+    /// Passes `ping` messages on to the other players as `other_player_pinged`.
+    Ping,
+    /// Takes `bump` messages and knocks the bumped duck back with `you_were_bumped`.
+    Bump,
+    /// Passes the `quack_voice` sent with a quack on to everyone else.
+    QuackVoice,
+}
+
+impl ProtocolFeature {
+    pub fn name(self) -> &'static str {
+        match self {
+            ProtocolFeature::ServerInfo => "server_info",
+            ProtocolFeature::AuthToken => "auth_token",
+            ProtocolFeature::InterestArea => "interest_area",
+            ProtocolFeature::Ping => "ping",
+            ProtocolFeature::Bump => "bump",
+            ProtocolFeature::QuackVoice => "quack_voice",
+        }
+    }
+}
+
+/// The data of the server's `hello` message.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ServerHello {
+    pub protocol_versions: Vec<u32>,
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+impl ServerHello {
+    pub fn supports_client(&self) -> bool {
+        self.protocol_versions.contains(&PROTOCOL_VERSION)
+    }
+
+    pub fn has_feature(&self, feature: ProtocolFeature) -> bool {
+        self.features.iter().any(|name| name == feature.name())
+    }
+}
+
+/// What the server we are playing on told us in its hello. `None` until the
+/// hello arrives, and for older servers that never send one.
+#[derive(Resource, Debug, Default)]
+pub struct ServerProtocol(pub Option<ServerHello>);
+
+impl ServerProtocol {
+    pub fn has_feature(&self, feature: ProtocolFeature) -> bool {
+        self.0
+            .as_ref()
+            .is_some_and(|hello| hello.has_feature(feature))
+    }
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct ServerHelloBevyEvent {
+    pub hello: ServerHello,
+}
+
+fn server_hello_handler(
+    mut event_reader: EventReader<ServerHelloBevyEvent>,
+    mut server_protocol: ResMut<ServerProtocol>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    for e in event_reader.read() {
+        info!(
+            "Server speaks protocol versions {:?} with features {:?}",
+            e.hello.protocol_versions, e.hello.features
+        );
+
+        if !e.hello.supports_client() {
+            warn!(
+                "Server doesn't speak protocol version {}, this client is out of date",
+                PROTOCOL_VERSION
+            );
+            // Leaving gameplay closes the socket.
+            next_screen.set(Screen::OutOfDate);
+        }
+
+        server_protocol.0 = Some(e.hello.clone());
+    }
+}
+
+/// Every connection gets its own hello, so drop the last server's when connecting.
+fn forget_server_protocol(
+    mut ev_connect: EventReader<WebSocketConnectionEvents>,
+    mut server_protocol: ResMut<ServerProtocol>,
+) {
+    if ev_connect.read().count() > 0 {
+        server_protocol.0 = None;
+    }
+}
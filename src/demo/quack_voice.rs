@@ -0,0 +1,202 @@
+//! Quack voices, so you can tell ducks apart by ear.
+//!
+//! A [`QuackVoice`] picks one of the quack samples and how fast (and so how
+//! high) it plays. You pick your own voice on the profile screen; it's saved
+//! with the rest of your [`PlayerProfile`] and, if the server advertises
+//! [`ProtocolFeature::QuackVoice`], sent along with your quacks. Other ducks
+//! quack with the voice the server passes on, or one picked from their uuid if
+//! it doesn't. The server's `quack_pitch` bends the voice further.
+//!
+//! [`ProtocolFeature::QuackVoice`]: super::protocol::ProtocolFeature::QuackVoice
+
+use bevy::prelude::*;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{asset_manifest::AssetManifest, asset_tracking::LoadResource, persistence};
+
+/// The key the [`PlayerProfile`] is saved under.
+pub const PROFILE_KEY: &str = "profile";
+
+/// How much each quack's speed varies at random, so repeats don't sound canned.
+const SPEED_JITTER: f32 = 0.04;
+/// The range we let the server's `quack_pitch` bend a voice in.
+const MIN_PITCH: f32 = 0.5;
+const MAX_PITCH: f32 = 2.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.load_resource::<QuackAssets>();
+    app.insert_resource(persistence::load::<PlayerProfile>(PROFILE_KEY).unwrap_or_default());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QuackSample {
+    Single,
+    Double,
+    Short,
+}
+
+impl QuackSample {
+    const ALL: [QuackSample; 3] = [QuackSample::Single, QuackSample::Double, QuackSample::Short];
+
+    /// The sound's name in the [`AssetManifest`].
+    fn sound_name(self) -> &'static str {
+        match self {
+            QuackSample::Single => "quack",
+            QuackSample::Double => "quack_double",
+            QuackSample::Short => "quack_short",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuackVoice {
+    #[default]
+    Classic,
+    Squeaky,
+    Honky,
+    Chatty,
+    Peep,
+}
+
+impl QuackVoice {
+    pub const ALL: [QuackVoice; 5] = [
+        QuackVoice::Classic,
+        QuackVoice::Squeaky,
+        QuackVoice::Honky,
+        QuackVoice::Chatty,
+        QuackVoice::Peep,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            QuackVoice::Classic => "Classic",
+            QuackVoice::Squeaky => "Squeaky",
+            QuackVoice::Honky => "Honky",
+            QuackVoice::Chatty => "Chatty",
+            QuackVoice::Peep => "Peep",
+        }
+    }
+
+    /// The name the voice goes by on the wire.
+    pub fn id(self) -> &'static str {
+        match self {
+            QuackVoice::Classic => "classic",
+            QuackVoice::Squeaky => "squeaky",
+            QuackVoice::Honky => "honky",
+            QuackVoice::Chatty => "chatty",
+            QuackVoice::Peep => "peep",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<QuackVoice> {
+        QuackVoice::ALL.into_iter().find(|voice| voice.id() == id)
+    }
+
+    /// A voice for a duck whose server doesn't tell us theirs, the same one
+    /// every time for the same duck.
+    pub fn for_player(player_uuid: &str) -> QuackVoice {
+        let hash = player_uuid.bytes().fold(0u32, |hash, byte| {
+            hash.wrapping_mul(31).wrapping_add(byte as u32)
+        });
+        QuackVoice::ALL[hash as usize % QuackVoice::ALL.len()]
+    }
+
+    fn sample(self) -> QuackSample {
+        match self {
+            QuackVoice::Classic | QuackVoice::Squeaky | QuackVoice::Honky => QuackSample::Single,
+            QuackVoice::Chatty => QuackSample::Double,
+            QuackVoice::Peep => QuackSample::Short,
+        }
+    }
+
+    fn speed(self) -> f32 {
+        match self {
+            QuackVoice::Classic => 1.0,
+            QuackVoice::Squeaky => 1.45,
+            QuackVoice::Honky => 0.75,
+            QuackVoice::Chatty => 1.1,
+            QuackVoice::Peep => 1.7,
+        }
+    }
+}
+
+/// Who we are, as far as this client is concerned. Saved between sessions.
+#[derive(Resource, Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerProfile {
+    pub quack_voice: QuackVoice,
+}
+
+#[derive(Resource, Asset, Reflect, Clone)]
+pub struct QuackAssets {
+    /// One per [`QuackSample`], in the order of [`QuackSample::ALL`].
+    #[dependency]
+    samples: Vec<Handle<AudioSource>>,
+}
+
+impl FromWorld for QuackAssets {
+    fn from_world(world: &mut World) -> Self {
+        let manifest = world.resource::<AssetManifest>();
+        let assets = world.resource::<AssetServer>();
+        Self {
+            samples: QuackSample::ALL
+                .iter()
+                .map(|sample| manifest.load_sound(assets, sample.sound_name()))
+                .collect(),
+        }
+    }
+}
+
+impl QuackAssets {
+    /// A quack in `voice`, bent by the server's `quack_pitch` (`0.0` if it
+    /// didn't send one). Play it with [`AudioCommands`], on the duck that
+    /// quacked if it isn't ours.
+    ///
+    /// [`AudioCommands`]: crate::audio::AudioCommands
+    pub fn quack(&self, voice: QuackVoice, pitch: f32) -> AudioBundle {
+        let pitch = if pitch > 0.0 {
+            pitch.clamp(MIN_PITCH, MAX_PITCH)
+        } else {
+            1.0
+        };
+        let jitter = 1.0 + thread_rng().gen_range(-SPEED_JITTER..=SPEED_JITTER);
+        AudioBundle {
+            source: self.samples[voice.sample() as usize].clone(),
+            settings: PlaybackSettings::DESPAWN.with_speed(voice.speed() * pitch * jitter),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_player_always_gets_the_same_voice() {
+        let uuid = "4f0c2a8e-93b1-4d55-8a4e-1c2f6b7d9e01";
+        assert_eq!(QuackVoice::for_player(uuid), QuackVoice::for_player(uuid));
+        assert_eq!(QuackVoice::for_player(""), QuackVoice::Classic);
+    }
+
+    #[test]
+    fn players_get_a_mix_of_voices() {
+        let mut heard = Vec::new();
+        for i in 0..50 {
+            let voice = QuackVoice::for_player(&format!("player-{i}"));
+            if !heard.contains(&voice) {
+                heard.push(voice);
+            }
+        }
+        assert_eq!(heard.len(), QuackVoice::ALL.len());
+    }
+
+    #[test]
+    fn voice_ids_round_trip() {
+        for voice in QuackVoice::ALL {
+            assert_eq!(QuackVoice::from_id(voice.id()), Some(voice));
+        }
+        assert_eq!(QuackVoice::from_id("moo"), None);
+    }
+}
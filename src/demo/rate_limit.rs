@@ -0,0 +1,186 @@
+//! Client-side throttling of outbound websocket messages, so a held key or a
+//! scripted clicker can't flood the server.
+//!
+//! Every [`C2SActionTypes`] with a limit gets its own token bucket: sending a
+//! message takes a token and tokens trickle back in at a fixed rate. Actions
+//! without a bucket (e.g. the join request) are never limited.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashMap};
+
+use super::websocket_connect::C2SActionTypes;
+
+/// The shortest time between two quacks.
+pub const QUACK_COOLDOWN: Duration = Duration::from_millis(800);
+
+/// How many move messages we send per second at most. Moves that don't make
+/// it out are added onto the next one.
+const MOVES_PER_SECOND: f32 = 20.0;
+
+const INTERACTS_PER_SECOND: f32 = 5.0;
+
+const VIEW_RECTS_PER_SECOND: f32 = 2.0;
+
+/// The shortest time between two minimap pings.
+pub const PING_COOLDOWN: Duration = Duration::from_secs(1);
+
+const BUMPS_PER_SECOND: f32 = 4.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<OutboundRateLimiter>();
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f32,
+    tokens_per_second: f32,
+    tokens: f32,
+    last_refill: Duration,
+}
+
+impl TokenBucket {
+    /// A full bucket holding `capacity` tokens, refilled at `tokens_per_second`.
+    pub fn new(capacity: f32, tokens_per_second: f32) -> Self {
+        Self {
+            capacity,
+            tokens_per_second,
+            tokens: capacity,
+            last_refill: Duration::ZERO,
+        }
+    }
+
+    fn available(&self, now: Duration) -> f32 {
+        let refilled = now.saturating_sub(self.last_refill).as_secs_f32() * self.tokens_per_second;
+        (self.tokens + refilled).min(self.capacity)
+    }
+
+    /// Takes a token if there is one.
+    pub fn try_acquire(&mut self, now: Duration) -> bool {
+        self.tokens = self.available(now);
+        self.last_refill = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// How much of the next token is still missing, from `1.0` right after the
+    /// last token was taken down to `0.0` once a token is available.
+    pub fn cooldown_fraction(&self, now: Duration) -> f32 {
+        (1.0 - self.available(now)).clamp(0.0, 1.0)
+    }
+}
+
+/// One [`TokenBucket`] per rate limited action type. Uses [`Time`] elapsed as the clock.
+#[derive(Resource, Debug)]
+pub struct OutboundRateLimiter {
+    buckets: HashMap<C2SActionTypes, TokenBucket>,
+}
+
+impl Default for OutboundRateLimiter {
+    fn default() -> Self {
+        let mut buckets = HashMap::default();
+        buckets.insert(
+            C2SActionTypes::Quack,
+            TokenBucket::new(1.0, 1.0 / QUACK_COOLDOWN.as_secs_f32()),
+        );
+        buckets.insert(
+            C2SActionTypes::Move,
+            TokenBucket::new(MOVES_PER_SECOND, MOVES_PER_SECOND),
+        );
+        buckets.insert(
+            C2SActionTypes::Interact,
+            TokenBucket::new(INTERACTS_PER_SECOND, INTERACTS_PER_SECOND),
+        );
+        buckets.insert(
+            C2SActionTypes::ViewRect,
+            TokenBucket::new(1.0, VIEW_RECTS_PER_SECOND),
+        );
+        buckets.insert(
+            C2SActionTypes::Ping,
+            TokenBucket::new(1.0, 1.0 / PING_COOLDOWN.as_secs_f32()),
+        );
+        buckets.insert(
+            C2SActionTypes::Bump,
+            TokenBucket::new(BUMPS_PER_SECOND, BUMPS_PER_SECOND),
+        );
+        Self { buckets }
+    }
+}
+
+impl OutboundRateLimiter {
+    /// Whether a message of this type may be sent now. Takes a token if so.
+    pub fn try_acquire(&mut self, action: C2SActionTypes, now: Duration) -> bool {
+        self.buckets
+            .get_mut(&action)
+            .is_none_or(|bucket| bucket.try_acquire(now))
+    }
+
+    /// Whether [`try_acquire`](Self::try_acquire) would succeed, without taking a token.
+    pub fn is_ready(&self, action: C2SActionTypes, now: Duration) -> bool {
+        self.cooldown_fraction(action, now) == 0.0
+    }
+
+    /// See [`TokenBucket::cooldown_fraction`]. Always `0.0` for unlimited actions.
+    pub fn cooldown_fraction(&self, action: C2SActionTypes, now: Duration) -> f32 {
+        self.buckets
+            .get(&action)
+            .map_or(0.0, |bucket| bucket.cooldown_fraction(now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_bucket_allows_a_burst_up_to_capacity() {
+        let mut bucket = TokenBucket::new(3.0, 1.0);
+        let now = Duration::ZERO;
+        assert!(bucket.try_acquire(now));
+        assert!(bucket.try_acquire(now));
+        assert!(bucket.try_acquire(now));
+        assert!(!bucket.try_acquire(now));
+    }
+
+    #[test]
+    fn empty_bucket_refuses_and_reports_a_full_cooldown() {
+        let mut bucket = TokenBucket::new(1.0, 1.0);
+        assert!(bucket.try_acquire(Duration::ZERO));
+        assert!(!bucket.try_acquire(Duration::ZERO));
+        assert_eq!(bucket.cooldown_fraction(Duration::ZERO), 1.0);
+    }
+
+    #[test]
+    fn tokens_refill_over_time_without_exceeding_capacity() {
+        let mut bucket = TokenBucket::new(2.0, 4.0);
+        assert!(bucket.try_acquire(Duration::ZERO));
+        assert!(bucket.try_acquire(Duration::ZERO));
+
+        // A quarter second brings back one token at four per second.
+        assert_eq!(bucket.cooldown_fraction(Duration::from_millis(125)), 0.5);
+        assert!(bucket.try_acquire(Duration::from_millis(250)));
+        assert!(!bucket.try_acquire(Duration::from_millis(250)));
+
+        // Waiting a long time only fills the bucket back up to capacity.
+        let later = Duration::from_secs(10);
+        assert!(bucket.try_acquire(later));
+        assert!(bucket.try_acquire(later));
+        assert!(!bucket.try_acquire(later));
+    }
+
+    #[test]
+    fn actions_without_a_bucket_are_never_limited() {
+        let mut limiter = OutboundRateLimiter::default();
+        for _ in 0..100 {
+            assert!(limiter.try_acquire(C2SActionTypes::Join, Duration::ZERO));
+        }
+        assert!(limiter.try_acquire(C2SActionTypes::Quack, Duration::ZERO));
+        assert!(!limiter.is_ready(C2SActionTypes::Quack, Duration::ZERO));
+        let cooled_down = QUACK_COOLDOWN + Duration::from_millis(10);
+        assert!(limiter.is_ready(C2SActionTypes::Quack, cooled_down));
+    }
+}
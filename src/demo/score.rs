@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 use serde::Deserialize;
 
+use super::hud_layout::ResponsiveHud;
 use super::websocket_connect::{UpdateLeaderboardBevyEvent, UpdateYourScoreBevyEvent};
 
 #[derive(Component)]
@@ -70,8 +71,6 @@ fn create_score_text(mut commands: Commands, asset_server: Res<AssetServer>) {
     let score_text = TextBundle {
         style: Style {
             position_type: PositionType::Absolute, // Absolute positioning
-            left: Val::Percent(3.),
-            top: Val::Percent(3.),
             ..Default::default()
         },
         text: Text::from_section(
@@ -88,8 +87,6 @@ fn create_score_text(mut commands: Commands, asset_server: Res<AssetServer>) {
     let position_text = TextBundle {
         style: Style {
             position_type: PositionType::Absolute, // Absolute positioning
-            left: Val::Percent(3.),
-            top: Val::Percent(6.),
             ..Default::default()
         },
         text: Text::from_section(
@@ -103,8 +100,20 @@ fn create_score_text(mut commands: Commands, asset_server: Res<AssetServer>) {
         ..Default::default()
     };
 
-    commands.spawn(score_text).insert(YourScoreText);
-    commands.spawn(position_text).insert(YourPositionText);
+    commands.spawn(score_text).insert((
+        YourScoreText,
+        ResponsiveHud::top_left(
+            (Val::Percent(3.), Val::Percent(3.)),
+            (Val::Percent(2.), Val::Px(8.)),
+        ),
+    ));
+    commands.spawn(position_text).insert((
+        YourPositionText,
+        ResponsiveHud::top_left(
+            (Val::Percent(3.), Val::Percent(6.)),
+            (Val::Percent(2.), Val::Px(38.)),
+        ),
+    ));
 }
 
 fn bevy_event_listener_update_your_score_text(
@@ -531,12 +540,14 @@ fn setup_leaderboard_table(
                 position_type: PositionType::Absolute, // Absolute positioning
                 row_gap: Val::Px(7.),
                 // column_gap: Val::Px(50.),
-                right: Val::Percent(3.),
-                top: Val::Percent(3.),
                 ..Default::default()
             },
             ..Default::default()
         })
+        .insert(ResponsiveHud::top_right(
+            (Val::Percent(3.), Val::Percent(3.)),
+            (Val::Percent(2.), Val::Px(8.)),
+        ))
         .with_children(|parent| {
             // Header Row
             parent
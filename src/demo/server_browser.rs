@@ -0,0 +1,481 @@
+//! Finds the servers a player can connect to and probes them for latency and
+//! player count, so the server select screen can show something useful.
+//!
+//! Candidate servers come from, in order:
+//! - a `?server=` query param on the page URL (web only),
+//! - the `BACKEND_WS_ENDPOINT` env var (read at runtime on native, at compile
+//!   time on web),
+//! - the [`ServerList`] config asset,
+//! - and finally [`DEFAULT_SERVER_URL`] if nothing else is configured.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::Instant};
+use serde::Deserialize;
+
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task};
+
+use crate::{asset_tracking::LoadResource, json_asset::RegisterJsonAsset};
+
+use super::{
+    protocol::{ProtocolFeature, ServerHello, HELLO_TIMEOUT},
+    websocket_connect::{GenericIncomingRequest, S2CActionTypes},
+};
+
+/// The local dev server, used when no other server is configured.
+pub const DEFAULT_SERVER_URL: &str = "ws://127.0.0.1:8000/ws";
+
+/// How long a probe waits for the server to answer the info request.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_json_asset::<ServerList>(&["servers.json"]);
+    app.load_resource::<ServerListAssets>();
+
+    app.init_resource::<ServerBrowser>();
+    app.add_event::<RefreshServerBrowser>();
+
+    app.add_systems(
+        Update,
+        (
+            refresh_server_browser.run_if(resource_exists::<ServerListAssets>),
+            poll_server_probes,
+        )
+            .chain(),
+    );
+}
+
+/// The servers listed in the `config/default.servers.json` asset.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct ServerList {
+    pub servers: Vec<ServerListEntry>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ServerListEntry {
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Resource, Asset, Reflect, Clone)]
+pub struct ServerListAssets {
+    #[dependency]
+    pub list: Handle<ServerList>,
+}
+
+impl ServerListAssets {
+    pub const PATH_SERVER_LIST: &'static str = "config/default.servers.json";
+}
+
+impl FromWorld for ServerListAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            list: assets.load(ServerListAssets::PATH_SERVER_LIST),
+        }
+    }
+}
+
+/// Every server we know about, along with the result of its latest probe.
+#[derive(Resource, Default, Debug)]
+pub struct ServerBrowser {
+    pub servers: Vec<KnownServer>,
+}
+
+#[derive(Debug, Clone)]
+pub struct KnownServer {
+    pub name: String,
+    pub url: String,
+    pub status: ServerStatus,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerStatus {
+    Probing,
+    Online {
+        latency: Duration,
+        /// `None` when the server didn't answer the info request in time.
+        player_count: Option<u64>,
+    },
+    Offline,
+    /// The server doesn't speak our protocol version.
+    Incompatible,
+}
+
+impl ServerStatus {
+    pub fn describe(&self) -> String {
+        match self {
+            ServerStatus::Probing => "checking...".to_string(),
+            ServerStatus::Online {
+                latency,
+                player_count: Some(player_count),
+            } => format!("{} ms, {} ducks", latency.as_millis(), player_count),
+            ServerStatus::Online {
+                latency,
+                player_count: None,
+            } => format!("{} ms", latency.as_millis()),
+            ServerStatus::Offline => "offline".to_string(),
+            ServerStatus::Incompatible => "needs a newer client, please refresh".to_string(),
+        }
+    }
+}
+
+/// Rebuild the server list from all configured sources and probe every server.
+#[derive(Event)]
+pub struct RefreshServerBrowser;
+
+#[derive(Debug, Deserialize)]
+struct ServerInfoResponseData {
+    player_count: u64,
+}
+
+fn refresh_server_browser(
+    mut commands: Commands,
+    mut refresh_events: EventReader<RefreshServerBrowser>,
+    mut browser: ResMut<ServerBrowser>,
+    server_list_assets: Res<ServerListAssets>,
+    server_lists: Res<Assets<ServerList>>,
+    old_probes: Query<Entity, With<ServerProbe>>,
+) {
+    if refresh_events.is_empty() {
+        return;
+    }
+    refresh_events.clear();
+
+    for entity in &old_probes {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    browser.servers.clear();
+
+    if let Some(url) = server_from_page_url() {
+        push_server(&mut browser, "From link", url);
+    }
+    if let Some(url) = server_from_env() {
+        push_server(&mut browser, "Default", url);
+    }
+    if let Some(list) = server_lists.get(&server_list_assets.list) {
+        for entry in &list.servers {
+            if !is_valid_server_url(&entry.url) {
+                warn!("Skipping server {} with invalid url {:?}", entry.name, entry.url);
+                continue;
+            }
+            push_server(&mut browser, &entry.name, entry.url.clone());
+        }
+    }
+    if browser.servers.is_empty() {
+        push_server(&mut browser, "Local", DEFAULT_SERVER_URL.to_string());
+    }
+
+    for (index, server) in browser.servers.iter_mut().enumerate() {
+        info!("Probing server {} at {}", server.name, server.url);
+        if let Err(e) = spawn_server_probe(&mut commands, index, server.url.clone()) {
+            warn!("Could not probe {}: {}", server.url, e);
+            server.status = ServerStatus::Offline;
+        }
+    }
+}
+
+/// Adds a server unless one with the same url is already listed.
+fn push_server(browser: &mut ServerBrowser, name: &str, url: String) {
+    if browser.servers.iter().any(|server| server.url == url) {
+        return;
+    }
+    browser.servers.push(KnownServer {
+        name: name.to_string(),
+        url,
+        status: ServerStatus::Probing,
+    });
+}
+
+/// Whether the url is something we can open a WebSocket to. Browsers throw
+/// on anything else, e.g. a space in the host or a `#fragment`.
+pub fn is_valid_server_url(url: &str) -> bool {
+    if url.contains('#') {
+        return false;
+    }
+    let Ok(uri) = url.parse::<http::Uri>() else {
+        return false;
+    };
+    matches!(uri.scheme_str(), Some("ws" | "wss"))
+        && uri.host().is_some_and(|host| !host.is_empty())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn server_from_env() -> Option<String> {
+    std::env::var("BACKEND_WS_ENDPOINT")
+        .ok()
+        .filter(|url| !url.is_empty())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn server_from_env() -> Option<String> {
+    option_env!("BACKEND_WS_ENDPOINT")
+        .filter(|url| !url.is_empty())
+        .map(|url| url.to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn server_from_page_url() -> Option<String> {
+    None
+}
+
+#[cfg(target_arch = "wasm32")]
+fn server_from_page_url() -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+    let params = web_sys::UrlSearchParams::new_with_str(&search).ok()?;
+    params
+        .get("server")
+        .filter(|url| is_valid_server_url(url))
+}
+
+fn build_server_info_request_msg() -> String {
+    serde_json::json!({
+        "action_type": "server_info",
+        "data": {},
+    })
+    .to_string()
+}
+
+/// The messages a probe cares about.
+enum ProbeMessage {
+    Hello(ServerHello),
+    ServerInfo { player_count: u64 },
+}
+
+fn parse_probe_message(text: &str) -> Option<ProbeMessage> {
+    let generic_msg: GenericIncomingRequest = serde_json::from_str(text).ok()?;
+    match generic_msg.action_type {
+        S2CActionTypes::Hello => serde_json::from_value(generic_msg.data)
+            .ok()
+            .map(ProbeMessage::Hello),
+        S2CActionTypes::ServerInfo => {
+            serde_json::from_value::<ServerInfoResponseData>(generic_msg.data)
+                .ok()
+                .map(|data| ProbeMessage::ServerInfo {
+                    player_count: data.player_count,
+                })
+        }
+        _ => None,
+    }
+}
+
+/// A short-lived connection used to measure a server's latency and player count.
+#[derive(Component)]
+struct ServerProbe {
+    index: usize,
+    #[cfg(not(target_arch = "wasm32"))]
+    task: Task<ServerStatus>,
+    #[cfg(target_arch = "wasm32")]
+    client: send_wrapper::SendWrapper<super::websocket_connect::wasm_websocket::Client>,
+    #[cfg(target_arch = "wasm32")]
+    started: Instant,
+    #[cfg(target_arch = "wasm32")]
+    latency: Option<Duration>,
+    #[cfg(target_arch = "wasm32")]
+    requested_info: bool,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn spawn_server_probe(commands: &mut Commands, index: usize, url: String) -> Result<(), String> {
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        probe_server(&url).unwrap_or_else(|e| {
+            info!("Probing {url} failed with: {e:?}");
+            ServerStatus::Offline
+        })
+    });
+    commands.spawn((Name::new("Server Probe"), ServerProbe { index, task }));
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn probe_server(url: &str) -> Result<ServerStatus, Box<dyn std::error::Error + Send + Sync>> {
+    use tungstenite::{stream::MaybeTlsStream, Message};
+
+    use super::websocket_connect::connect_with_timeout;
+
+    let started = Instant::now();
+    let (mut socket, _) = connect_with_timeout(url, PROBE_TIMEOUT)?;
+    let latency = started.elapsed();
+
+    match socket.get_mut() {
+        MaybeTlsStream::Plain(p) => p.set_read_timeout(Some(HELLO_TIMEOUT))?,
+        MaybeTlsStream::Rustls(stream_owned) => {
+            stream_owned.get_mut().set_read_timeout(Some(HELLO_TIMEOUT))?
+        }
+        _ => {}
+    };
+
+    // Wait for the hello to see whether we can ask for the player count. Other
+    // messages (e.g. leaderboard broadcasts) may arrive in between, so read
+    // until we have what we need or run out of time.
+    let mut player_count = None;
+    let mut requested_info = false;
+    while started.elapsed() < latency + PROBE_TIMEOUT {
+        if !requested_info && started.elapsed() > latency + HELLO_TIMEOUT {
+            // An older server that never says hello.
+            break;
+        }
+        match socket.read() {
+            Ok(Message::Text(text)) => match parse_probe_message(&text) {
+                Some(ProbeMessage::Hello(hello)) => {
+                    if !hello.supports_client() {
+                        let _ = socket.close(None);
+                        return Ok(ServerStatus::Incompatible);
+                    }
+                    if !hello.has_feature(ProtocolFeature::ServerInfo) {
+                        break;
+                    }
+                    socket.send(Message::text(build_server_info_request_msg()))?;
+                    requested_info = true;
+                }
+                Some(ProbeMessage::ServerInfo { player_count: count }) => {
+                    player_count = Some(count);
+                    break;
+                }
+                None => {}
+            },
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => {}
+            Err(_) => break,
+        }
+    }
+    let _ = socket.close(None);
+
+    Ok(ServerStatus::Online {
+        latency,
+        player_count,
+    })
+}
+
+/// Fails if the browser won't even try to open a socket to `url`.
+#[cfg(target_arch = "wasm32")]
+fn spawn_server_probe(commands: &mut Commands, index: usize, url: String) -> Result<(), String> {
+    let client = super::websocket_connect::wasm_websocket::Client::new(&url)
+        .map_err(|e| format!("{e:?}"))?;
+    commands.spawn((
+        Name::new("Server Probe"),
+        ServerProbe {
+            index,
+            client,
+            started: Instant::now(),
+            latency: None,
+            requested_info: false,
+        },
+    ));
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn poll_server_probes(
+    mut commands: Commands,
+    mut browser: ResMut<ServerBrowser>,
+    mut probes: Query<(Entity, &mut ServerProbe)>,
+) {
+    for (entity, mut probe) in &mut probes {
+        if let Some(status) = block_on(future::poll_once(&mut probe.task)) {
+            if let Some(server) = browser.servers.get_mut(probe.index) {
+                server.status = status;
+            }
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn poll_server_probes(
+    mut commands: Commands,
+    mut browser: ResMut<ServerBrowser>,
+    mut probes: Query<(Entity, &mut ServerProbe)>,
+) {
+    for (entity, mut probe) in &mut probes {
+        let ready_state = probe.client.socket.ready_state();
+        let status = match (ready_state, probe.latency) {
+            (web_sys::WebSocket::CONNECTING, _) if probe.started.elapsed() < PROBE_TIMEOUT => None,
+            (web_sys::WebSocket::OPEN, None) => {
+                probe.latency = Some(probe.started.elapsed());
+                None
+            }
+            (web_sys::WebSocket::OPEN, Some(latency)) => {
+                let messages: Vec<_> = probe.client.recv_queue.borrow_mut().drain(..).collect();
+                let mut status = None;
+                for message in messages.iter().filter_map(|m| m.as_string()) {
+                    match parse_probe_message(&message) {
+                        Some(ProbeMessage::Hello(hello)) if !hello.supports_client() => {
+                            status = Some(ServerStatus::Incompatible);
+                        }
+                        Some(ProbeMessage::Hello(hello)) => {
+                            if hello.has_feature(ProtocolFeature::ServerInfo) {
+                                let _ = probe
+                                    .client
+                                    .socket
+                                    .send_with_str(&build_server_info_request_msg());
+                                probe.requested_info = true;
+                            } else {
+                                status = Some(ServerStatus::Online {
+                                    latency,
+                                    player_count: None,
+                                });
+                            }
+                        }
+                        Some(ProbeMessage::ServerInfo { player_count }) => {
+                            status = Some(ServerStatus::Online {
+                                latency,
+                                player_count: Some(player_count),
+                            });
+                        }
+                        None => {}
+                    }
+                }
+
+                let waited = probe.started.elapsed();
+                let timed_out = if probe.requested_info {
+                    waited > latency + PROBE_TIMEOUT
+                } else {
+                    // An older server that never says hello.
+                    waited > latency + HELLO_TIMEOUT
+                };
+                status.or_else(|| {
+                    timed_out.then_some(ServerStatus::Online {
+                        latency,
+                        player_count: None,
+                    })
+                })
+            }
+            _ => Some(ServerStatus::Offline),
+        };
+
+        if let Some(status) = status {
+            if let Some(server) = browser.servers.get_mut(probe.index) {
+                server.status = status;
+            }
+            let _ = probe.client.socket.close();
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_websocket_urls() {
+        assert!(is_valid_server_url("ws://127.0.0.1:8000/ws"));
+        assert!(is_valid_server_url("wss://quackers.example.com/ws/"));
+    }
+
+    #[test]
+    fn rejects_urls_a_browser_would_throw_on() {
+        assert!(!is_valid_server_url("ws://"));
+        assert!(!is_valid_server_url("ws://a b"));
+        assert!(!is_valid_server_url("ws://example.com/ws#top"));
+        assert!(!is_valid_server_url("http://example.com/ws"));
+        assert!(!is_valid_server_url("example.com"));
+    }
+}
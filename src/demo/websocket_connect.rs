@@ -29,7 +29,7 @@ use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
 use wasm_bindgen_futures::spawn_local;
 
 // Client to Server types
-#[derive(Debug, PartialEq, EnumString, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, Serialize)]
 pub enum C2SActionTypes {
     #[strum(serialize = "join", serialize = "j")]
     Join,
@@ -43,6 +43,18 @@ pub enum C2SActionTypes {
     #[strum(serialize = "interact", serialize = "i")]
     Interact,
 
+    #[strum(serialize = "server_info", serialize = "si")]
+    ServerInfo,
+
+    #[strum(serialize = "view_rect", serialize = "vr")]
+    ViewRect,
+
+    #[strum(serialize = "ping", serialize = "p")]
+    Ping,
+
+    #[strum(serialize = "bump", serialize = "b")]
+    Bump,
+
     #[strum(serialize = "empty", serialize = "e")]
     Empty, // used as a default in order to ignore invalid inputs without panicing
 }
@@ -83,6 +95,21 @@ pub enum S2CActionTypes {
 
     #[strum(serialize = "leaderboard_update", serialize = "lu")]
     LeaderboardUpdate,
+
+    #[strum(serialize = "server_info", serialize = "si")]
+    ServerInfo,
+
+    #[strum(serialize = "auth_rejected", serialize = "ar")]
+    AuthRejected,
+
+    #[strum(serialize = "hello", serialize = "h")]
+    Hello,
+
+    #[strum(serialize = "other_player_pinged", serialize = "opp")]
+    OtherPlayerPinged,
+
+    #[strum(serialize = "you_were_bumped", serialize = "ywb")]
+    YouWereBumped,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -114,7 +141,7 @@ pub(super) fn plugin(app: &mut App) {
     app.add_event::<UserDisconnectedBevyEvent>();
 
     // app.add_systems(Startup, setup_scene)
-    app.add_systems(Startup, begin_ws_connect);
+    // Connecting now happens once a server is picked on the server select screen.
     app.add_systems(Update, setup_connection);
     app.add_systems(Update, handle_tasks);
     app.add_event::<WebSocketConnectionEvents>();
@@ -128,6 +155,8 @@ pub(super) fn plugin(app: &mut App) {
     // app.add_systems(Update, setup_connection);
     // app.add_systems(Update, handle_tasks);
     // app.add_systems(Update, receive_ws_msg);
+
+    app.add_systems(OnExit(Screen::Gameplay), disconnect_from_server);
 }
 
 // #[derive(Component)]
@@ -139,8 +168,8 @@ pub(super) fn plugin(app: &mut App) {
 // );
 
 #[derive(Event)]
-enum WebSocketConnectionEvents {
-    SetupConnection,
+pub enum WebSocketConnectionEvents {
+    SetupConnection { url: String },
 }
 
 #[derive(Event, Debug, Clone)]
@@ -298,10 +327,18 @@ pub struct GenericIncomingRequest {
 //     }
 // }
 
+use crate::audio::AudioCommands;
+use crate::music::DucksMusic;
+use crate::demo::auth::{AuthRejectedBevyEvent, LastConnectionError};
+use crate::demo::bumping::YouWereBumpedBevyEvent;
+use crate::demo::minimap::OtherPlayerPingedBevyEvent;
 use crate::demo::other_player::DuckDirection;
-use crate::demo::websocket_join_msg::build_join_request_msg;
+use crate::demo::outbound_queue::OutboundQueue;
+use crate::demo::protocol::{ServerHello, ServerHelloBevyEvent};
+use crate::demo::websocket_join_msg::JoinPending;
+use crate::screens::Screen;
 
-use super::{cracker::YouGotCrackerSoundFx, other_player::OtherPlayerData};
+use super::{cracker::CrackerAssets, other_player::OtherPlayerData};
 
 // use std::{
 //     sync::Mutex},
@@ -311,7 +348,7 @@ use super::{cracker::YouGotCrackerSoundFx, other_player::OtherPlayerData};
 use iyes_perf_ui::{entries::PerfUiBundle, PerfUiPlugin};
 
 #[cfg(not(target_arch = "wasm32"))]
-use tungstenite::{connect, http::Response, stream::MaybeTlsStream, Message, WebSocket};
+use tungstenite::{http::Response, stream::MaybeTlsStream, Message, WebSocket};
 
 // fn main() {
 //     #[cfg(not(target_arch = "wasm32"))]
@@ -341,7 +378,7 @@ use tungstenite::{connect, http::Response, stream::MaybeTlsStream, Message, WebS
 // }
 
 #[cfg(target_arch = "wasm32")]
-mod wasm_websocket {
+pub(super) mod wasm_websocket {
     use std::{cell::RefCell, collections::VecDeque, rc::Rc};
 
     use bevy::log::info;
@@ -361,17 +398,17 @@ mod wasm_websocket {
     }
 
     impl Client {
-        pub fn new(url: &str) -> send_wrapper::SendWrapper<Self> {
+        /// Fails if the browser won't open a socket to `url`, e.g. because
+        /// it isn't a valid WebSocket url.
+        pub fn new(url: &str) -> Result<send_wrapper::SendWrapper<Self>, JsValue> {
             info!("Opening wasm websocket");
             let recv_queue = Rc::new(RefCell::new(VecDeque::new()));
-            let socket = web_sys::WebSocket::new(url).expect("Failed to create WebSocket object");
+            let socket = web_sys::WebSocket::new(url)?;
             socket.set_binary_type(BinaryType::Arraybuffer);
             let open_cb: Closure<dyn FnMut(_)> = Closure::new(|_event: Event| {
                 web_sys::console::log_1(&"Connection opened".into());
             });
-            socket
-                .add_event_listener_with_callback("open", open_cb.as_ref().dyn_ref().unwrap())
-                .unwrap();
+            socket.add_event_listener_with_callback("open", open_cb.as_ref().dyn_ref().unwrap())?;
             let message_cb: Closure<dyn FnMut(_)> = Closure::new({
                 let recv_queue = Rc::clone(&recv_queue);
                 move |event: MessageEvent| {
@@ -389,15 +426,16 @@ mod wasm_websocket {
                     // }
                 }
             });
-            socket
-                .add_event_listener_with_callback("message", message_cb.as_ref().dyn_ref().unwrap())
-                .unwrap();
-            send_wrapper::SendWrapper::new(Client {
+            socket.add_event_listener_with_callback(
+                "message",
+                message_cb.as_ref().dyn_ref().unwrap(),
+            )?;
+            Ok(send_wrapper::SendWrapper::new(Client {
                 socket,
                 recv_queue,
                 _open_cb: open_cb,
                 _message_cb: message_cb,
-            })
+            }))
         }
     }
 }
@@ -412,22 +450,45 @@ pub struct WebSocketClient(
     ),
 );
 
+impl WebSocketClient {
+    /// Whether the socket can take messages yet.
+    pub fn is_open(&self) -> bool {
+        // The native client is only inserted once the handshake is done.
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            true
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.0.socket.ready_state() == web_sys::WebSocket::OPEN
+        }
+    }
+
+    pub fn send_text(&mut self, text: String) -> Result<(), String> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            match self.0 .0.send(Message::text(text)) {
+                Ok(_) => Ok(()),
+                // Still queued in tungstenite's write buffer, it goes out with the next flush
+                Err(tungstenite::Error::Io(e)) if e.kind() == ErrorKind::WouldBlock => Ok(()),
+                Err(e) => Err(e.to_string()),
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.0
+                .socket
+                .send_with_str(&text)
+                .map_err(|err| format!("{:?}", err))
+        }
+    }
+}
+
 // #[derive(Event)]
 // enum WebSocketConnectionEvents {
 //     SetupConnection,
 // }
 
-fn begin_ws_connect(
-    // input: Res<ButtonInput<KeyCode>>,
-    mut ev_connect: EventWriter<WebSocketConnectionEvents>,
-) {
-    // if input.just_pressed(KeyCode::Space) {
-    // set up connection
-
-    ev_connect.send(WebSocketConnectionEvents::SetupConnection);
-    // }
-}
-
 // use thiserror::Error;
 // use web_sys::MessageEvent;
 // #[cfg(target_arch = "wasm32")]
@@ -468,46 +529,89 @@ impl MyMessage {
     }
 }
 
+/// Like [`tungstenite::connect`], but gives up on a server that doesn't answer within
+/// `timeout` instead of waiting on it forever.
+#[cfg(not(target_arch = "wasm32"))]
+// Same error as `tungstenite::connect`, so callers can handle both alike.
+#[allow(clippy::result_large_err)]
+pub fn connect_with_timeout(
+    url: &str,
+    timeout: Duration,
+) -> Result<(WebSocket<MaybeTlsStream<TcpStream>>, Response<Option<Vec<u8>>>), tungstenite::Error>
+{
+    use std::net::ToSocketAddrs;
+    use tungstenite::{
+        client::{uri_mode, IntoClientRequest},
+        error::UrlError,
+        handshake::HandshakeError,
+        stream::Mode,
+    };
+
+    let request = url.into_client_request()?;
+    let uri = request.uri();
+    let host = uri
+        .host()
+        .ok_or(tungstenite::Error::Url(UrlError::NoHostName))?
+        // IPv6 hosts come in brackets.
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let port = uri.port_u16().unwrap_or(match uri_mode(uri)? {
+        Mode::Plain => 80,
+        Mode::Tls => 443,
+    });
+
+    let mut last_error = None;
+    let stream = (host, port)
+        .to_socket_addrs()?
+        .find_map(|address| {
+            TcpStream::connect_timeout(&address, timeout)
+                .map_err(|e| last_error = Some(e))
+                .ok()
+        })
+        .ok_or_else(|| last_error.unwrap_or_else(|| ErrorKind::NotFound.into()))?;
+    // The handshake blocks on reads and writes, so they need a timeout too.
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    tungstenite::client_tls(request, stream).map_err(|e| match e {
+        HandshakeError::Failure(e) => e,
+        HandshakeError::Interrupted(_) => tungstenite::Error::Io(ErrorKind::TimedOut.into()),
+    })
+}
+
 fn setup_connection(
     mut ev_connect: EventReader<WebSocketConnectionEvents>,
     mut commands: Commands,
+    #[cfg(target_arch = "wasm32")] mut last_connection_error: ResMut<LastConnectionError>,
+    #[cfg(target_arch = "wasm32")] mut next_screen: ResMut<NextState<Screen>>,
 ) {
     for ev in ev_connect.read() {
         match ev {
-            WebSocketConnectionEvents::SetupConnection => {
+            WebSocketConnectionEvents::SetupConnection { url } => {
                 info!("Setting up connection!");
+                info!("endpoint is: {}", url);
 
-                let mut url = "".to_string();
-                let default_url = "ws://127.0.0.1:8000/ws".to_string();
-
-                #[cfg(not(target_arch = "wasm32"))]
-                {
-                    url = std::env::var("BACKEND_WS_ENDPOINT".to_string()).unwrap_or(default_url);
-                    info!("endpoint is: {}", url)
-                }
-
-                #[cfg(target_arch = "wasm32")]
-                {
-                    let value = env!("BACKEND_WS_ENDPOINT");
-                    if value == "" {
-                        url = default_url;
-                    } else {
-                        url = value.to_string();
-                    }
-                }
-
-                let entity = commands.spawn_empty().id();
+                let url = url.clone();
+                let entity = commands
+                    .spawn((
+                        Name::new("WebSocket Client"),
+                        JoinPending::default(),
+                        OutboundQueue::default(),
+                    ))
+                    .id();
 
                 // Define the message to send
                 let message = MyMessage::new("Hello, WebSocket!".to_string());
                 // let json_message = serde_json::to_string(&message).unwrap();
-                let json_message = build_join_request_msg("foo".to_string());
 
                 #[cfg(not(target_arch = "wasm32"))]
                 {
                     let pool = AsyncComputeTaskPool::get();
                     let task = pool.spawn(async move {
-                        let mut client = connect(&url)?;
+                        let mut client = connect_with_timeout(
+                            &url,
+                            super::websocket_join_msg::CONNECT_TIMEOUT,
+                        )?;
                         match client.0.get_mut() {
                             MaybeTlsStream::Plain(p) => p.set_nonblocking(true)?,
                             MaybeTlsStream::Rustls(stream_owned) => {
@@ -538,7 +642,17 @@ fn setup_connection(
                 {
                     web_sys::console::log_1(&"//1 wasm connecting".into());
                     // Use the ewebsock or wasm-websocket client to send the message
-                    let client = wasm_websocket::Client::new(&url);
+                    let client = match wasm_websocket::Client::new(&url) {
+                        Ok(client) => client,
+                        Err(e) => {
+                            warn!("Connection failed with: {e:?}");
+                            commands.entity(entity).despawn_recursive();
+                            last_connection_error.0 = Some(format!("Could not connect to {url}"));
+                            // Leaving gameplay drops the pending connection.
+                            next_screen.set(Screen::ServerSelect);
+                            continue;
+                        }
+                    };
                     web_sys::console::log_1(&"//1 wasm connected".into());
 
                     // let message = MyMessage::new("Hello, WebSocket!".to_string());
@@ -571,9 +685,11 @@ fn setup_connection(
 
 fn handle_tasks(
     mut commands: Commands,
-    mut transform_tasks: Query<&mut WebSocketConnectionSetupTask>,
+    mut transform_tasks: Query<(Entity, &mut WebSocketConnectionSetupTask)>,
+    mut last_connection_error: ResMut<LastConnectionError>,
+    mut next_screen: ResMut<NextState<Screen>>,
 ) {
-    for mut task in &mut transform_tasks {
+    for (entity, mut task) in &mut transform_tasks {
         if let Some(result) = block_on(future::poll_once(&mut task.0)) {
             // append the returned command queue to have it execute later
             match result {
@@ -581,7 +697,15 @@ fn handle_tasks(
                     commands.append(&mut commands_queue);
                 }
                 Err(e) => {
-                    info!("Connection failed with: {e:?}");
+                    warn!("Connection failed with: {e:?}");
+                    last_connection_error.0 =
+                        Some(format!("Could not connect to the server: {e}"));
+                    // Leaving gameplay drops the connection.
+                    next_screen.set(Screen::ServerSelect);
+                    // A finished task can't be polled again
+                    commands
+                        .entity(entity)
+                        .remove::<WebSocketConnectionSetupTask>();
                 }
             }
         }
@@ -654,8 +778,11 @@ fn recv_info(
     mut bevy_event_writer_user_disconnected: EventWriter<UserDisconnectedBevyEvent>,
     mut bevy_event_writer_update_your_score: EventWriter<UpdateYourScoreBevyEvent>,
     mut bevy_event_writer_update_leaderboard: EventWriter<UpdateLeaderboardBevyEvent>,
-    audio: Res<YouGotCrackerSoundFx>,
-    audio_assets: Res<Assets<AudioSource>>,
+    mut bevy_event_writer_auth_rejected: EventWriter<AuthRejectedBevyEvent>,
+    mut bevy_event_writer_server_hello: EventWriter<ServerHelloBevyEvent>,
+    mut bevy_event_writer_other_player_pinged: EventWriter<OtherPlayerPingedBevyEvent>,
+    mut bevy_event_writer_you_were_bumped: EventWriter<YouWereBumpedBevyEvent>,
+    cracker_assets: Option<Res<CrackerAssets>>,
     mut commands: Commands,
     // mut bevy_event_writer_generic_message: EventWriter<GenericIncomingRequest>,
 ) {
@@ -692,12 +819,15 @@ fn recv_info(
                         &mut bevy_event_writer_other_player_joined,
                         &mut bevy_event_writer_other_player_quacked,
                         &mut bevy_event_writer_other_player_moved,
-                        &audio_assets,
-                        &audio,
+                        cracker_assets.as_deref(),
                         &mut bevy_event_writer_move_crackers,
                         &mut bevy_event_writer_update_your_score,
                         &mut bevy_event_writer_user_disconnected,
                         &mut bevy_event_writer_update_leaderboard,
+                        &mut bevy_event_writer_auth_rejected,
+                        &mut bevy_event_writer_server_hello,
+                        &mut bevy_event_writer_other_player_pinged,
+                        &mut bevy_event_writer_you_were_bumped,
                     );
 
                     // bevy_event_writer_you_joined.send(generic_msg);
@@ -741,12 +871,15 @@ fn recv_info(
                         &mut bevy_event_writer_other_player_joined,
                         &mut bevy_event_writer_other_player_quacked,
                         &mut bevy_event_writer_other_player_moved,
-                        &audio_assets,
-                        &audio,
+                        cracker_assets.as_deref(),
                         &mut bevy_event_writer_move_crackers,
                         &mut bevy_event_writer_update_your_score,
                         &mut bevy_event_writer_user_disconnected,
                         &mut bevy_event_writer_update_leaderboard,
+                        &mut bevy_event_writer_auth_rejected,
+                        &mut bevy_event_writer_server_hello,
+                        &mut bevy_event_writer_other_player_pinged,
+                        &mut bevy_event_writer_you_were_bumped,
                     );
 
                     // match generic_msg.action_type {
@@ -870,12 +1003,15 @@ fn fun_name(
     bevy_event_writer_other_player_joined: &mut EventWriter<'_, OtherPlayerJoinedWsReceived>,
     bevy_event_writer_other_player_quacked: &mut EventWriter<'_, OtherPlayerQuackedWsReceived>,
     bevy_event_writer_other_player_moved: &mut EventWriter<'_, OtherPlayerMovedWsReceived>,
-    audio_assets: &Res<'_, Assets<AudioSource>>,
-    audio: &Res<'_, YouGotCrackerSoundFx>,
+    cracker_assets: Option<&CrackerAssets>,
     bevy_event_writer_move_crackers: &mut EventWriter<'_, MoveCrackersBevyEvent>,
     bevy_event_writer_update_your_score: &mut EventWriter<'_, UpdateYourScoreBevyEvent>,
     bevy_event_writer_user_disconnected: &mut EventWriter<'_, UserDisconnectedBevyEvent>,
     bevy_event_writer_update_leaderboard: &mut EventWriter<'_, UpdateLeaderboardBevyEvent>,
+    bevy_event_writer_auth_rejected: &mut EventWriter<'_, AuthRejectedBevyEvent>,
+    bevy_event_writer_server_hello: &mut EventWriter<'_, ServerHelloBevyEvent>,
+    bevy_event_writer_other_player_pinged: &mut EventWriter<'_, OtherPlayerPingedBevyEvent>,
+    bevy_event_writer_you_were_bumped: &mut EventWriter<'_, YouWereBumpedBevyEvent>,
 ) {
     match generic_msg.action_type {
         S2CActionTypes::YouJoined => {
@@ -951,15 +1087,15 @@ fn fun_name(
             );
 
             // Play special you got crackers sound
-            if let Some(_) = audio_assets.get(&audio.sound_handle) {
-                // Spawn an audio source to play the sound
-                commands.spawn(AudioSourceBundle {
-                    source: audio.sound_handle.clone(),
-                    ..Default::default()
-                });
-                println!("Playing your quack sound.");
-            } else {
-                println!("Audio not loaded yet.");
+            // Loaded on the loading screen, before we could have joined.
+            if let Some(cracker_assets) = cracker_assets {
+                commands
+                    .play_sound_effect(AudioBundle {
+                        source: cracker_assets.you_got_crackers.clone(),
+                        settings: PlaybackSettings::DESPAWN,
+                    })
+                    .insert(DucksMusic);
+                debug!("Playing you got crackers sound.");
             }
 
             // --> send event for crackers to move
@@ -1024,6 +1160,98 @@ fn fun_name(
             });
             info!("Received 'LeaderboardUpdate' message from ws server!");
         }
+        S2CActionTypes::ServerInfo => {
+            // Only the server browser's probe connections care about this one.
+            info!("Received 'ServerInfo' message from ws server!");
+        }
+        S2CActionTypes::AuthRejected => {
+            let auth_rejected_msg_data =
+                serde_json::from_value(generic_msg.data.clone()).unwrap_or_else(|op| {
+                    info!("Failed to parse incoming websocket message: {}", op);
+                    AuthRejectedBevyEvent {
+                        reason: "unknown".to_string(),
+                    }
+                });
+
+            bevy_event_writer_auth_rejected.send(auth_rejected_msg_data);
+            info!("Received 'AuthRejected' message from ws server!");
+        }
+        S2CActionTypes::Hello => {
+            info!("Received 'Hello' message from ws server!");
+            match serde_json::from_value::<ServerHello>(generic_msg.data.clone()) {
+                Ok(hello) => {
+                    bevy_event_writer_server_hello.send(ServerHelloBevyEvent { hello });
+                }
+                // Not knowing what it speaks, treat it like an older server
+                // that never says hello, without any optional features.
+                Err(op) => warn!(
+                    "Failed to parse the server's hello, treating it as a legacy server: {}",
+                    op
+                ),
+            }
+        }
+        S2CActionTypes::OtherPlayerPinged => {
+            let other_player_pinged_msg_data = serde_json::from_value(generic_msg.data.clone())
+                .unwrap_or_else(|op| {
+                    info!("Failed to parse incoming websocket message: {}", op);
+                    OtherPlayerPingedBevyEvent {
+                        player_uuid: "error".to_string(),
+                        x_position: 0.,
+                        y_position: 0.,
+                    }
+                });
+
+            bevy_event_writer_other_player_pinged.send(other_player_pinged_msg_data);
+            info!("Received 'OtherPlayerPinged' message from ws server!");
+        }
+        S2CActionTypes::YouWereBumped => {
+            let you_were_bumped_msg_data = serde_json::from_value(generic_msg.data.clone())
+                .unwrap_or_else(|op| {
+                    info!("Failed to parse incoming websocket message: {}", op);
+                    YouWereBumpedBevyEvent {
+                        by_player_uuid: "error".to_string(),
+                        knockback_x: 0.,
+                        knockback_y: 0.,
+                    }
+                });
+
+            bevy_event_writer_you_were_bumped.send(you_were_bumped_msg_data);
+            info!("Received 'YouWereBumped' message from ws server!");
+        }
+    }
+}
+
+/// Close the socket when leaving gameplay so picking a server again starts fresh.
+fn disconnect_from_server(
+    mut commands: Commands,
+    mut clients: Query<(Entity, &mut WebSocketClient)>,
+    pending: Query<
+        Entity,
+        (
+            Or<(With<WebSocketConnectionSetupTask>, With<JoinPending>)>,
+            Without<WebSocketClient>,
+        ),
+    >,
+) {
+    for (entity, mut client) in &mut clients {
+        info!("Disconnecting from server");
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let _ = client.0 .0.close(None);
+            let _ = client.0 .0.flush();
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let _ = client.0.socket.close();
+        }
+
+        commands.entity(entity).despawn_recursive();
+    }
+
+    // Drop any connection that is still being set up.
+    for entity in &pending {
+        commands.entity(entity).despawn_recursive();
     }
 }
 
@@ -1,9 +1,16 @@
-use std::io::ErrorKind;
+use std::time::Duration;
 
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::Instant};
 use serde::{Deserialize, Serialize};
 
-use super::websocket_connect::WebSocketClient;
+use crate::screens::Screen;
+
+use super::{
+    auth::{AuthToken, LastConnectionError, LOGIN_TIMEOUT},
+    outbound_queue::OutboundQueue,
+    protocol::{ProtocolFeature, ServerProtocol, HELLO_TIMEOUT, PROTOCOL_VERSION},
+    websocket_connect::{C2SActionTypes, WebSocketClient},
+};
 // use tungstenite::Message;
 
 #[derive(Serialize, Deserialize)]
@@ -17,61 +24,84 @@ pub(super) fn plugin(app: &mut App) {
     app.add_event::<JoinRequestEvent>();
 
     // app.add_systems(Update, join_request_bevy_event_listener);
-    app.add_systems(Update, play_btn_ws_kickoff);
+    app.add_systems(Update, (queue_join_request, give_up_on_stalled_join));
+}
+
+/// How long connecting (signing in, opening the socket and waiting for the
+/// hello) may take before we give up and go back to the server select screen.
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(LOGIN_TIMEOUT.as_secs() + 5);
+
+/// Marks a client that hasn't queued its join request yet. Nothing else is
+/// sent until it has, so the join is always the first message out.
+#[derive(Component)]
+pub struct JoinPending {
+    created_at: Instant,
+    opened_at: Option<Instant>,
 }
 
-// use super::websocket_connect::WebSocketClient;
+impl Default for JoinPending {
+    fn default() -> Self {
+        Self {
+            created_at: Instant::now(),
+            opened_at: None,
+        }
+    }
+}
 
-fn play_btn_ws_kickoff(
+// Queues the join request for the server picked on the server select screen
+fn queue_join_request(
     mut commands: Commands,
-    interaction_query: Query<(Entity, &Interaction, &Name), Changed<Interaction>>,
-    mut q: Query<(&mut WebSocketClient,)>,
-    // audio: Res<QuackAudio>,
-    // audio_assets: Res<Assets<AudioSource>>,
+    mut q: Query<(Entity, &mut OutboundQueue, &mut JoinPending, Option<&WebSocketClient>)>,
+    auth_token: Res<AuthToken>,
+    server_protocol: Res<ServerProtocol>,
 ) {
-    for (_entity, interaction, name) in &interaction_query {
-        if matches!(interaction, Interaction::Pressed) {
-            if name.to_string() == "Play Button".to_string() {
-                println!("Making Play btn api call!");
-
-                for (mut client,) in q.iter_mut() {
-                    let json_message = build_join_request_msg("foo".to_string());
-
-                    #[cfg(not(target_arch = "wasm32"))]
-                    {
-                        client
-                            .0
-                             .0
-                            .send(tungstenite::Message::Text(json_message))
-                            .expect("sending play btn kickoff natively failed");
-                    }
-                    #[cfg(target_arch = "wasm32")]
-                    {
-                        match client.0.socket.send_with_str(&json_message) {
-                            Ok(_) => web_sys::console::log_1(&"Message sent successfully".into()),
-                            Err(err) => web_sys::console::log_1(
-                                &format!("Error sending message: {:?}", err).into(),
-                            ),
-                        }
-                    }
-                }
-            }
+    // Hold off until the login endpoint has answered so we join with a fresh token.
+    if auth_token.is_fetching() {
+        return;
+    }
+
+    for (entity, mut queue, mut join_pending, client) in q.iter_mut() {
+        // Give the server a chance to say hello before joining, so we know what it supports.
+        if !client.is_some_and(|client| client.is_open()) {
+            continue;
+        }
+        let opened_at = *join_pending.opened_at.get_or_insert_with(Instant::now);
+        match &server_protocol.0 {
+            None if opened_at.elapsed() < HELLO_TIMEOUT => continue,
+            // We're on our way to the out of date screen.
+            Some(hello) if !hello.supports_client() => continue,
+            _ => {}
         }
 
-        // if matches!(interaction, Interaction::Pressed) {
-        //     println!("clicked quack btn!");
-
-        // if let Some(_) = audio_assets.get(&audio.sound_handle) {
-        // Spawn an audio source to play the sound
-        // commands.spawn(AudioSourceBundle {
-        //     source: audio.sound_handle.clone(), // Clone the handle to use it
-        //     ..Default::default()                // Use default values for other fields
-        // });
-        // println!("Playing YouGotCrackers sound.");
-        // } else {
-        //     println!("Audio not loaded yet.");
-        // }
-        // }
+        // Every server gets our protocol version, but only hand the token to
+        // servers that say they check it.
+        let auth_token = auth_token
+            .token
+            .clone()
+            .filter(|_| server_protocol.has_feature(ProtocolFeature::AuthToken));
+        let json_message = build_join_request_msg("foo".to_string(), auth_token);
+        queue.push_front(C2SActionTypes::Join, json_message);
+        info!("Join request ws msg queued");
+
+        commands.entity(entity).remove::<JoinPending>();
+    }
+}
+
+/// A server that never lets us join would leave us staring at an empty pond.
+fn give_up_on_stalled_join(
+    pending: Query<&JoinPending>,
+    mut last_connection_error: ResMut<LastConnectionError>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    if pending
+        .iter()
+        .any(|join_pending| join_pending.created_at.elapsed() > CONNECT_TIMEOUT)
+    {
+        warn!("Gave up connecting after {:?}", CONNECT_TIMEOUT);
+        last_connection_error.0 =
+            Some("Could not connect to the server: it took too long".to_string());
+        // Leaving gameplay drops the connection.
+        next_screen.set(Screen::ServerSelect);
     }
 }
 
@@ -103,7 +133,10 @@ pub struct JoinRequestEvent(pub String);
 
 #[derive(serde::Serialize)]
 struct JoinRequestData {
+    protocol_version: u32,
     friendly_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auth_token: Option<String>,
 }
 
 #[derive(serde::Serialize)]
@@ -112,11 +145,13 @@ struct JoinRequest {
     data: JoinRequestData,
 }
 
-pub fn build_join_request_msg(friendly_name: String) -> String {
+pub fn build_join_request_msg(friendly_name: String, auth_token: Option<String>) -> String {
     let join_request_hardcoded = JoinRequest {
         action_type: "join".to_string(),
         data: JoinRequestData {
-            friendly_name: friendly_name,
+            protocol_version: PROTOCOL_VERSION,
+            friendly_name,
+            auth_token,
         },
     };
 
@@ -1,7 +1,9 @@
 use bevy::prelude::*;
-use std::io::ErrorKind;
 
-use super::websocket_connect::WebSocketClient;
+use super::{
+    outbound_queue::OutboundQueue, rate_limit::OutboundRateLimiter,
+    websocket_connect::C2SActionTypes,
+};
 // use tungstenite::Message;
 
 pub(super) fn plugin(app: &mut App) {
@@ -14,38 +16,33 @@ pub(super) fn plugin(app: &mut App) {
 #[derive(Event)]
 pub struct MoveRequestEvent(pub f32, pub f32);
 
-// Listens for bevy events for ws messages and fires them off to the server
+// Listens for bevy events for ws messages and queues them up for the server.
+// Moves that the rate limiter holds back are added up and sent together.
 fn move_request_bevy_event_listener(
     mut ev_join_request: EventReader<MoveRequestEvent>,
-    mut entities_with_client: Query<(&mut WebSocketClient,)>,
+    mut outbound_queues: Query<&mut OutboundQueue>,
+    mut rate_limiter: ResMut<OutboundRateLimiter>,
+    time: Res<Time>,
+    mut unsent_move: Local<Vec2>,
 ) {
     for ev in ev_join_request.read() {
-        println!("heard move request bevy event");
-        for mut client in entities_with_client.iter_mut() {
-            println!("sending move request ws msg");
-            let message = build_move_request_msg(ev.0.clone(), ev.1.clone());
+        *unsent_move += Vec2::new(ev.0, ev.1);
+    }
+
+    // Nobody to tell, so don't let moves pile up for the next connection.
+    if outbound_queues.is_empty() {
+        *unsent_move = Vec2::ZERO;
+        return;
+    }
+    if *unsent_move == Vec2::ZERO
+        || !rate_limiter.try_acquire(C2SActionTypes::Move, time.elapsed())
+    {
+        return;
+    }
+    let move_to_send = std::mem::take(&mut *unsent_move);
 
-            #[cfg(not(target_arch = "wasm32"))]
-            {
-                match client.0 .0 .0.send(tungstenite::Message::text(message)) {
-                    Ok(_) => info!("Join request ws msg successfully sent to server!"),
-                    Err(tungstenite::Error::Io(e)) if e.kind() == ErrorKind::WouldBlock => { /* ignore */
-                    }
-                    Err(e) => {
-                        warn!("Could not send the message: {e:?}");
-                    }
-                }
-            }
-            #[cfg(target_arch = "wasm32")]
-            {
-                match client.0.0.socket.send_with_str(&message) {
-                    Ok(_) => web_sys::console::log_1(&"Message sent successfully".into()),
-                    Err(err) => {
-                        web_sys::console::log_1(&format!("Error sending message: {:?}", err).into())
-                    }
-                }
-            }
-        }
+    for mut queue in outbound_queues.iter_mut() {
+        queue.push_move(move_to_send);
     }
 }
 
@@ -61,7 +58,7 @@ struct MoveRequest {
     data: MoveRequestData,
 }
 
-fn build_move_request_msg(x_direction: f32, y_direction: f32) -> String {
+pub(super) fn build_move_request_msg(x_direction: f32, y_direction: f32) -> String {
     let join_request_hardcoded = MoveRequest {
         action_type: "move".to_string(),
         data: MoveRequestData {
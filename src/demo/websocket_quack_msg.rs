@@ -14,12 +14,16 @@ use crate::theme::widgets::Containers;
 use crate::demo::other_player::{unpack_duck_color, NewJoinerDataWithAllPlayers};
 use crate::{
     asset_tracking::LoadResource,
-    demo::{movement::MovementController, player_animation::PlayerAnimation},
+    demo::movement::MovementController,
     screens::Screen,
 };
 
+use super::outbound_queue::OutboundQueue;
+use super::protocol::{ProtocolFeature, ServerProtocol};
+use super::quack_voice::{PlayerProfile, QuackVoice};
+use super::rate_limit::OutboundRateLimiter;
 use super::websocket_connect::{
-    MoveCrackersBevyEvent, OtherPlayerJoinedWsReceived, WebSocketClient, YouJoinedWsReceived
+    C2SActionTypes, MoveCrackersBevyEvent, OtherPlayerJoinedWsReceived, YouJoinedWsReceived,
 };
 
 #[derive(Event)]
@@ -32,36 +36,28 @@ pub(super) fn plugin(app: &mut App) {
 
 fn quack_request_bevy_event_listener(
     mut ev_join_request: EventReader<QuackRequestEvent>,
-    mut entities_with_client: Query<(&mut WebSocketClient,)>,
+    mut outbound_queues: Query<&mut OutboundQueue>,
+    mut rate_limiter: ResMut<OutboundRateLimiter>,
+    time: Res<Time>,
+    server_protocol: Res<ServerProtocol>,
+    profile: Res<PlayerProfile>,
 ) {
+    // Older servers only know the empty quack request.
+    let quack_voice = server_protocol
+        .has_feature(ProtocolFeature::QuackVoice)
+        .then_some(profile.quack_voice);
 
-    for ev in ev_join_request.read() {
+    for _ev in ev_join_request.read() {
         println!("heard quack request bevy event");
-        
-        for mut client in entities_with_client.iter_mut() {
-            println!("sending quack request ws msg");
-            let message = build_quack_request_msg();
 
-            #[cfg(not(target_arch = "wasm32"))]
-            {
-                match client.0 .0 .0.send(tungstenite::Message::text(message)) {
-                    Ok(_) => info!("Join request ws msg successfully sent to server!"),
-                    Err(tungstenite::Error::Io(e)) if e.kind() == std::io::ErrorKind::WouldBlock => { /* ignore */
-                    }
-                    Err(e) => {
-                        warn!("Could not send the message: {e:?}");
-                    }
-                }
-            }
-            #[cfg(target_arch = "wasm32")]
-            {
-                match client.0.0.socket.send_with_str(&message) {
-                    Ok(_) => web_sys::console::log_1(&"Message sent successfully".into()),
-                    Err(err) => {
-                        web_sys::console::log_1(&format!("Error sending message: {:?}", err).into())
-                    }
-                }
-            }
+        if !rate_limiter.try_acquire(C2SActionTypes::Quack, time.elapsed()) {
+            info!("Quack is cooling down, not sending it");
+            continue;
+        }
+
+        for mut queue in outbound_queues.iter_mut() {
+            debug!("queueing quack request ws msg");
+            queue.push(C2SActionTypes::Quack, build_quack_request_msg(quack_voice));
         }
     }
 }
@@ -69,7 +65,8 @@ fn quack_request_bevy_event_listener(
 
 #[derive(serde::Serialize)]
 struct QuackRequestData {
-
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quack_voice: Option<&'static str>,
 }
 
 #[derive(serde::Serialize)]
@@ -78,10 +75,12 @@ struct QuackRequest {
     data: QuackRequestData,
 }
 
-fn build_quack_request_msg() -> String {
+fn build_quack_request_msg(quack_voice: Option<QuackVoice>) -> String {
     let join_request_hardcoded = QuackRequest {
         action_type: "quack".to_string(),
-        data: QuackRequestData {},
+        data: QuackRequestData {
+            quack_voice: quack_voice.map(QuackVoice::id),
+        },
     };
 
     serde_json::ser::to_string(&join_request_hardcoded).unwrap_or_else(|_op| {
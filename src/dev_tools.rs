@@ -9,7 +9,13 @@ use bevy::{
     prelude::*,
 };
 
-use crate::screens::Screen;
+use crate::{
+    demo::{
+        background::WorldClock,
+        collision::{Collider, Obstacle},
+    },
+    screens::Screen,
+};
 
 pub(super) fn plugin(app: &mut App) {
     // Log `Screen` state transitions.
@@ -21,10 +27,63 @@ pub(super) fn plugin(app: &mut App) {
         Update,
         toggle_debug_ui.run_if(input_just_pressed(TOGGLE_KEY)),
     );
+
+    // Toggle drawing colliders.
+    app.init_resource::<ShowColliders>();
+    app.add_systems(
+        Update,
+        (
+            toggle_colliders.run_if(input_just_pressed(TOGGLE_COLLIDERS_KEY)),
+            draw_colliders.run_if(|show: Res<ShowColliders>| show.0),
+        )
+            .chain(),
+    );
+
+    // Skip ahead a few hours to check the day and night tints.
+    app.add_systems(
+        Update,
+        skip_time_of_day.run_if(input_just_pressed(SKIP_TIME_OF_DAY_KEY)),
+    );
 }
 
 const TOGGLE_KEY: KeyCode = KeyCode::Backquote;
+const TOGGLE_COLLIDERS_KEY: KeyCode = KeyCode::F3;
+const SKIP_TIME_OF_DAY_KEY: KeyCode = KeyCode::F4;
 
 fn toggle_debug_ui(mut options: ResMut<UiDebugOptions>) {
     options.toggle();
 }
+
+#[derive(Resource, Default)]
+struct ShowColliders(bool);
+
+fn toggle_colliders(mut show: ResMut<ShowColliders>) {
+    show.0 = !show.0;
+}
+
+fn draw_colliders(
+    mut gizmos: Gizmos,
+    colliders: Query<(&GlobalTransform, &Collider, Has<Obstacle>)>,
+) {
+    for (transform, collider, is_obstacle) in &colliders {
+        let position = transform.translation().truncate();
+        let color = if is_obstacle {
+            Color::srgb(1.0, 0.3, 0.3)
+        } else {
+            Color::srgb(0.3, 1.0, 0.3)
+        };
+        match collider {
+            Collider::Aabb { half_size } => {
+                gizmos.rect_2d(position, 0.0, *half_size * 2.0, color);
+            }
+            Collider::Circle { radius } => {
+                gizmos.circle_2d(position, *radius, color);
+            }
+        }
+    }
+}
+
+fn skip_time_of_day(mut clock: ResMut<WorldClock>) {
+    clock.advance(3.0);
+    info!("Skipped ahead to {:.1}h", clock.hour);
+}
@@ -0,0 +1,69 @@
+//! A tiny generic loader for assets stored as JSON files.
+//!
+//! Each asset type gets its own compound extension (e.g. `servers.json`) so
+//! several JSON asset types can live side by side in the `assets` folder.
+
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+pub trait RegisterJsonAsset {
+    /// Register `T` as an [`Asset`] that is deserialized from JSON files
+    /// ending in one of the given `extensions`.
+    fn register_json_asset<T: Asset + DeserializeOwned>(
+        &mut self,
+        extensions: &'static [&'static str],
+    ) -> &mut Self;
+}
+
+impl RegisterJsonAsset for App {
+    fn register_json_asset<T: Asset + DeserializeOwned>(
+        &mut self,
+        extensions: &'static [&'static str],
+    ) -> &mut Self {
+        self.init_asset::<T>();
+        self.register_asset_loader(JsonAssetLoader::<T> {
+            extensions,
+            _marker: PhantomData,
+        })
+    }
+}
+
+struct JsonAssetLoader<T> {
+    extensions: &'static [&'static str],
+    _marker: PhantomData<fn() -> T>,
+}
+
+#[derive(Error, Debug)]
+pub enum JsonAssetLoaderError {
+    #[error("Could not read asset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl<T: Asset + DeserializeOwned> AssetLoader for JsonAssetLoader<T> {
+    type Asset = T;
+    type Settings = ();
+    type Error = JsonAssetLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<T, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+}
@@ -1,8 +1,12 @@
+mod asset_manifest;
 mod asset_tracking;
 pub mod audio;
 mod demo;
 #[cfg(feature = "dev")]
 mod dev_tools;
+mod json_asset;
+mod music;
+mod persistence;
 mod screens;
 mod theme;
 
@@ -12,11 +16,6 @@ use bevy::{
     prelude::*, window::WindowResolution,
 };
 
-/// Spatial audio uses the distance to attenuate the sound volume. In 2D with the default camera,
-/// 1 pixel is 1 unit of distance, so we use a scale so that 100 pixels is 1 unit of distance for
-/// audio.
-const AUDIO_SCALE: f32 = 1. / 200.0;
-
 pub struct AppPlugin;
 
 impl Plugin for AppPlugin {
@@ -47,24 +46,28 @@ impl Plugin for AppPlugin {
                         fit_canvas_to_parent: true,
                         resolution: WindowResolution::new(600.0, 800.0),
                         prevent_default_event_handling: true,
-                        resizable: false,
+                        resizable: true,
                         ..default()
                     }
                     .into(),
                     ..default()
                 })
                 .set(AudioPlugin {
+                    // The player's own volume settings (`audio.rs`) scale on top of this.
                     global_volume: GlobalVolume {
                         volume: Volume::new(0.3),
                     },
-                    default_spatial_scale: SpatialScale::new_2d(AUDIO_SCALE),
+                    default_spatial_scale: SpatialScale::new_2d(audio::AUDIO_SCALE),
                     ..default()
                 }),
         );
 
         // Add other plugins.
         app.add_plugins((
+            asset_manifest::plugin,
             asset_tracking::plugin,
+            audio::plugin,
+            music::plugin,
             demo::plugin,
             screens::plugin,
             theme::plugin,
@@ -94,6 +97,8 @@ fn spawn_camera(mut commands: Commands) {
     commands.spawn((
         Name::new("Camera"),
         Camera2dBundle::default(),
+        // Follows our duck once it spawns.
+        demo::camera::CameraController::default(),
         // Render all UI to this camera.
         // Not strictly necessary since we only use one camera,
         // but if we don't use this component, our UI will disappear as soon
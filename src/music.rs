@@ -0,0 +1,288 @@
+//! Decides what music plays, and crossfades between tracks.
+//!
+//! Each [`Screen`] has a playlist, and gameplay has a more intense one for
+//! while you're in first place (see [`MusicMood`]). Tracks loop, and a
+//! playlist with more than one track moves on to its next track every
+//! [`TRACK_ROTATION_SECS`]. Whenever the track changes, the old one fades out
+//! as the new one fades in, so changing screens never cuts the music off.
+//!
+//! Music also ducks under sounds tagged with [`DucksMusic`], so the important
+//! ones cut through.
+
+use bevy::prelude::*;
+
+use crate::{
+    asset_manifest::AssetManifest,
+    asset_tracking::LoadResource,
+    audio::{AudioCommands, Gain},
+    screens::Screen,
+};
+
+/// How long the old track takes to fade out, and the new one to fade in.
+const CROSSFADE_SECS: f32 = 2.0;
+/// How long a track plays before a playlist moves on to the next one.
+const TRACK_ROTATION_SECS: f32 = 180.0;
+
+/// How loud music is, compared to normal, while it's ducked.
+const DUCKED_GAIN: f32 = 0.35;
+/// How fast music ducks under a sound, in gain per second.
+const DUCK_ATTACK_SPEED: f32 = 6.0;
+/// How fast music comes back once the sound is over, in gain per second.
+const DUCK_RELEASE_SPEED: f32 = 1.5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<MusicMood>();
+    app.init_resource::<MusicDirector>();
+    app.init_resource::<MusicDucking>();
+    app.load_resource::<MusicAssets>();
+
+    app.add_systems(OnExit(Screen::Gameplay), reset_music_mood);
+    app.add_systems(
+        Update,
+        (
+            direct_music.run_if(resource_exists::<MusicAssets>),
+            duck_music,
+            fade_music,
+        )
+            .chain(),
+    );
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Track {
+    FluffingADuck,
+    MonkeysSpinningMonkeys,
+}
+
+impl Track {
+    const ALL: [Track; 2] = [Track::FluffingADuck, Track::MonkeysSpinningMonkeys];
+
+    /// The track's name in the [`AssetManifest`].
+    fn sound_name(self) -> &'static str {
+        match self {
+            Track::FluffingADuck => "fluffing_a_duck",
+            Track::MonkeysSpinningMonkeys => "monkeys_spinning_monkeys",
+        }
+    }
+}
+
+/// How the game is going, for picking the gameplay playlist.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MusicMood {
+    #[default]
+    Calm,
+    /// We're in first place.
+    Leading,
+}
+
+fn playlist(screen: &Screen, mood: MusicMood) -> &'static [Track] {
+    match screen {
+        Screen::Splash | Screen::Loading | Screen::LoadFailed => &[],
+        Screen::Title
+        | Screen::Settings
+        | Screen::Profile
+        | Screen::ServerSelect
+        | Screen::OutOfDate => &[Track::FluffingADuck, Track::MonkeysSpinningMonkeys],
+        Screen::Credits => &[Track::MonkeysSpinningMonkeys],
+        Screen::Gameplay => match mood {
+            MusicMood::Calm => &[Track::FluffingADuck],
+            MusicMood::Leading => &[Track::MonkeysSpinningMonkeys],
+        },
+    }
+}
+
+/// Add this to a playing sound to turn the music down while it plays.
+#[derive(Component, Debug, Default)]
+pub struct DucksMusic;
+
+#[derive(Resource, Asset, Reflect, Clone)]
+pub struct MusicAssets {
+    /// One per [`Track`], in the order of [`Track::ALL`].
+    #[dependency]
+    tracks: Vec<Handle<AudioSource>>,
+}
+
+impl MusicAssets {
+    fn track(&self, track: Track) -> Handle<AudioSource> {
+        self.tracks[track as usize].clone()
+    }
+}
+
+impl FromWorld for MusicAssets {
+    fn from_world(world: &mut World) -> Self {
+        let manifest = world.resource::<AssetManifest>();
+        let assets = world.resource::<AssetServer>();
+        Self {
+            tracks: Track::ALL
+                .iter()
+                .map(|track| manifest.load_sound(assets, track.sound_name()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Resource)]
+struct MusicDirector {
+    playlist: &'static [Track],
+    now_playing: Option<(Track, Entity)>,
+    rotation: Timer,
+}
+
+impl Default for MusicDirector {
+    fn default() -> Self {
+        Self {
+            playlist: &[],
+            now_playing: None,
+            rotation: Timer::from_seconds(TRACK_ROTATION_SECS, TimerMode::Repeating),
+        }
+    }
+}
+
+/// How loud all music is because of ducking, from [`DUCKED_GAIN`] to `1.0`.
+#[derive(Resource)]
+struct MusicDucking {
+    level: f32,
+}
+
+impl Default for MusicDucking {
+    fn default() -> Self {
+        Self { level: 1.0 }
+    }
+}
+
+/// Fades a track the director started in or out.
+#[derive(Component)]
+struct MusicFader {
+    level: f32,
+    /// `1.0` while the track is playing, `0.0` once it's on its way out.
+    target: f32,
+}
+
+fn reset_music_mood(mut mood: ResMut<MusicMood>) {
+    *mood = MusicMood::Calm;
+}
+
+fn direct_music(
+    mut commands: Commands,
+    time: Res<Time>,
+    screen: Res<State<Screen>>,
+    mood: Res<MusicMood>,
+    music_assets: Res<MusicAssets>,
+    mut director: ResMut<MusicDirector>,
+    mut faders: Query<&mut MusicFader>,
+) {
+    let playlist = playlist(screen.get(), *mood);
+    let now_playing = director.now_playing.map(|(track, _)| track);
+
+    if director.playlist != playlist {
+        director.playlist = playlist;
+        director.rotation.reset();
+        // Keep going with what's playing if the new playlist has it too.
+        if now_playing.is_some_and(|track| playlist.contains(&track)) {
+            return;
+        }
+        let next = playlist.first().copied();
+        crossfade_to(
+            &mut commands,
+            &mut director,
+            &mut faders,
+            &music_assets,
+            next,
+        );
+        return;
+    }
+
+    if playlist.len() > 1 && director.rotation.tick(time.delta()).just_finished() {
+        let next_index = now_playing
+            .and_then(|track| playlist.iter().position(|&t| t == track))
+            .map_or(0, |index| (index + 1) % playlist.len());
+        let next = Some(playlist[next_index]);
+        crossfade_to(
+            &mut commands,
+            &mut director,
+            &mut faders,
+            &music_assets,
+            next,
+        );
+    }
+}
+
+/// Fade out what's playing and fade in `track`, or nothing.
+fn crossfade_to(
+    commands: &mut Commands,
+    director: &mut MusicDirector,
+    faders: &mut Query<&mut MusicFader>,
+    music_assets: &MusicAssets,
+    track: Option<Track>,
+) {
+    if let Some((_, entity)) = director.now_playing.take() {
+        if let Ok(mut fader) = faders.get_mut(entity) {
+            fader.target = 0.0;
+        }
+    }
+
+    let Some(track) = track else {
+        return;
+    };
+    info!("Now playing {:?}", track);
+    // Not scoped to a screen, so it can carry on into the next one.
+    let entity = commands
+        .play_music(AudioBundle {
+            source: music_assets.track(track),
+            settings: PlaybackSettings::LOOP,
+        })
+        .insert((
+            Name::new(format!("Music: {:?}", track)),
+            Gain(0.0),
+            MusicFader {
+                level: 0.0,
+                target: 1.0,
+            },
+        ))
+        .id();
+    director.now_playing = Some((track, entity));
+}
+
+fn duck_music(
+    time: Res<Time>,
+    mut ducking: ResMut<MusicDucking>,
+    // Sounds only have a sink while they're playing.
+    ducking_sounds: Query<
+        (),
+        (
+            With<DucksMusic>,
+            Or<(With<AudioSink>, With<SpatialAudioSink>)>,
+        ),
+    >,
+) {
+    let (target, speed) = if ducking_sounds.is_empty() {
+        (1.0, DUCK_RELEASE_SPEED)
+    } else {
+        (DUCKED_GAIN, DUCK_ATTACK_SPEED)
+    };
+    let level = move_towards(ducking.level, target, speed * time.delta_seconds());
+    if ducking.level != level {
+        ducking.level = level;
+    }
+}
+
+fn fade_music(
+    mut commands: Commands,
+    time: Res<Time>,
+    ducking: Res<MusicDucking>,
+    mut music: Query<(Entity, &mut MusicFader, &mut Gain)>,
+) {
+    let step = time.delta_seconds() / CROSSFADE_SECS;
+    for (entity, mut fader, mut gain) in &mut music {
+        fader.level = move_towards(fader.level, fader.target, step);
+        if fader.target == 0.0 && fader.level == 0.0 {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        gain.set_if_neq(Gain(fader.level * ducking.level));
+    }
+}
+
+fn move_towards(current: f32, target: f32, max_step: f32) -> f32 {
+    current + (target - current).clamp(-max_step, max_step)
+}
@@ -0,0 +1,73 @@
+//! Keeps small bits of state, like the audio settings, between sessions.
+//!
+//! Values are stored as JSON under a key: in `localStorage` on the web, and
+//! as `<key>.json` in the user's config folder on native.
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// The prefix for `localStorage` keys, and the name of the native config folder.
+const APP_NAME: &str = "quackers";
+
+/// Read the value saved under `key`, if there is one and it still parses.
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let json = read(key)?;
+    serde_json::from_str(&json)
+        .inspect_err(|e| warn!("Ignoring saved {}: {}", key, e))
+        .ok()
+}
+
+/// Save `value` under `key`, replacing what was there.
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    let json = match serde_json::to_string(value) {
+        Ok(json) => json,
+        Err(e) => {
+            warn!("Couldn't convert {} to JSON: {}", key, e);
+            return;
+        }
+    };
+    if let Err(e) = write(key, &json) {
+        warn!("Couldn't save {}: {}", key, e);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(key: &str) -> Option<String> {
+    local_storage()?.get_item(&format!("{APP_NAME}.{key}")).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(key: &str, json: &str) -> Result<(), String> {
+    local_storage()
+        .ok_or("no localStorage")?
+        .set_item(&format!("{APP_NAME}.{key}"), json)
+        .map_err(|e| format!("{e:?}"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn config_dir() -> Option<std::path::PathBuf> {
+    use std::{env, path::PathBuf};
+
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join(APP_NAME))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(key: &str) -> Option<String> {
+    std::fs::read_to_string(config_dir()?.join(format!("{key}.json"))).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(key: &str, json: &str) -> Result<(), String> {
+    let dir = config_dir().ok_or("no config folder")?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    std::fs::write(dir.join(format!("{key}.json")), json).map_err(|e| e.to_string())
+}
@@ -3,19 +3,14 @@
 use bevy::{input::common_conditions::input_just_pressed, prelude::*};
 
 use crate::{
-    asset_tracking::LoadResource, audio::Music, demo::{level::spawn_level as spawn_level_command, websocket_join_msg::JoinRequestEvent},
+    demo::{level::spawn_level as spawn_level_command, websocket_join_msg::JoinRequestEvent},
     screens::Screen,
 };
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Screen::Gameplay), spawn_level);
 
-    app.load_resource::<GameplayMusic>();
-
-    // TODO - add ack world music
-
-    // app.add_systems(OnEnter(Screen::Gameplay), play_gameplay_music);
-    // app.add_systems(OnExit(Screen::Gameplay), stop_music);
+    // Gameplay music is picked by the music director, see `music.rs`.
 
     app.add_systems(
         Update,
@@ -30,43 +25,6 @@ fn spawn_level(mut commands: Commands,  mut join_request_event_writer: EventWrit
     join_request_event_writer.send(JoinRequestEvent("hello".to_string()));
 }
 
-#[derive(Resource, Asset, Reflect, Clone)]
-pub struct GameplayMusic {
-    #[dependency]
-    handle: Handle<AudioSource>,
-    entity: Option<Entity>,
-}
-
-impl FromWorld for GameplayMusic {
-    fn from_world(world: &mut World) -> Self {
-        let assets = world.resource::<AssetServer>();
-        Self {
-            handle: assets.load("audio/music/Fluffing A Duck.ogg"),
-            entity: None,
-        }
-    }
-}
-
-fn play_gameplay_music(mut commands: Commands, mut music: ResMut<GameplayMusic>) {
-    // music.entity = Some(
-        // commands
-        //     .spawn((
-        //         AudioBundle {
-        //             source: music.handle.clone(),
-        //             settings: PlaybackSettings::LOOP,
-        //         },
-        //         Music,
-        //     ))
-        //     .id(),
-    // );
-}
-
-fn stop_music(mut commands: Commands, mut music: ResMut<GameplayMusic>) {
-    if let Some(entity) = music.entity.take() {
-        commands.entity(entity).despawn_recursive();
-    }
-}
-
 fn return_to_title_screen(mut next_screen: ResMut<NextState<Screen>>) {
     next_screen.set(Screen::Title);
 }
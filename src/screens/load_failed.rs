@@ -0,0 +1,46 @@
+//! Shown when some of the game's assets couldn't be loaded, e.g. a file
+//! missing from the deployed build or a dropped connection on the web.
+
+use bevy::prelude::*;
+
+use crate::{
+    asset_tracking::{LoadingProgress, RetryFailedAssets},
+    screens::Screen,
+    theme::prelude::*,
+};
+
+/// How many failed assets we list before summing up the rest.
+const MAX_LISTED_FAILURES: usize = 5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::LoadFailed), spawn_load_failed_screen);
+}
+
+fn spawn_load_failed_screen(mut commands: Commands, progress: Res<LoadingProgress>) {
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::LoadFailed))
+        .with_children(|children| {
+            children.header("Couldn't load the game");
+            for failure in progress.failures.iter().take(MAX_LISTED_FAILURES) {
+                children.label(format!("{}: {}", failure.path, failure.error));
+            }
+            if progress.failures.len() > MAX_LISTED_FAILURES {
+                children.label(format!(
+                    "...and {} more",
+                    progress.failures.len() - MAX_LISTED_FAILURES
+                ));
+            }
+
+            children.button("Retry").observe(retry_loading);
+        });
+}
+
+fn retry_loading(
+    _trigger: Trigger<OnPress>,
+    mut retry_events: EventWriter<RetryFailedAssets>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    retry_events.send(RetryFailedAssets);
+    next_screen.set(Screen::Loading);
+}
@@ -4,9 +4,14 @@
 use bevy::prelude::*;
 
 use crate::{
-    demo::player::PlayerAssets,
-    screens::{credits::CreditsMusic, gameplay::GameplayMusic, Screen},
-    theme::{interaction::InteractionAssets, prelude::*},
+    asset_tracking::LoadingProgress,
+    demo::{
+        bumping::BumpAssets, cracker::CrackerAssets, duck_animation::DuckAssets,
+        level::LevelAssets, quack_voice::QuackAssets, server_browser::ServerListAssets,
+    },
+    music::MusicAssets,
+    screens::Screen,
+    theme::{interaction::InteractionAssets, palette::*, prelude::*},
 };
 
 pub(super) fn plugin(app: &mut App) {
@@ -14,34 +19,114 @@ pub(super) fn plugin(app: &mut App) {
 
     app.add_systems(
         Update,
-        continue_to_title_screen.run_if(in_state(Screen::Loading).and_then(all_assets_loaded)),
+        (
+            (update_loading_progress, show_load_failure).run_if(in_state(Screen::Loading)),
+            continue_to_title_screen.run_if(in_state(Screen::Loading).and_then(all_assets_loaded)),
+        ),
     );
 }
 
+#[derive(Component)]
+struct LoadingLabel;
+
+#[derive(Component)]
+struct LoadingBarFill;
+
 fn spawn_loading_screen(mut commands: Commands) {
     commands
         .ui_root()
         .insert(StateScoped(Screen::Loading))
         .with_children(|children| {
-            children.label("Loading...").insert(Style {
-                justify_content: JustifyContent::Center,
-                ..default()
-            });
+            children
+                .label("Loading...")
+                .insert(Style {
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                })
+                .insert(LoadingLabel);
+
+            children
+                .spawn((
+                    Name::new("Loading Bar"),
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Px(500.0),
+                            height: Val::Px(20.0),
+                            ..default()
+                        },
+                        background_color: BackgroundColor(NODE_BACKGROUND),
+                        ..default()
+                    },
+                ))
+                .with_children(|children| {
+                    children.spawn((
+                        Name::new("Loading Bar Fill"),
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: BackgroundColor(LABEL_TEXT),
+                            ..default()
+                        },
+                        LoadingBarFill,
+                    ));
+                });
         });
 }
 
+fn update_loading_progress(
+    progress: Res<LoadingProgress>,
+    mut labels: Query<&mut Text, With<LoadingLabel>>,
+    mut fills: Query<&mut Style, With<LoadingBarFill>>,
+) {
+    if !progress.is_changed() {
+        return;
+    }
+
+    for mut text in &mut labels {
+        text.sections[0].value = match &progress.current {
+            // The counts are of resources like `DuckAssets`, not single assets.
+            Some(current) => format!(
+                "Loading {} ({} groups left)",
+                current,
+                progress.total - progress.loaded
+            ),
+            None => "Loading...".to_string(),
+        };
+    }
+    for mut style in &mut fills {
+        style.width = Val::Percent(progress.fraction() * 100.0);
+    }
+}
+
+fn show_load_failure(progress: Res<LoadingProgress>, mut next_screen: ResMut<NextState<Screen>>) {
+    if progress.has_failed() {
+        next_screen.set(Screen::LoadFailed);
+    }
+}
+
 fn continue_to_title_screen(mut next_screen: ResMut<NextState<Screen>>) {
     next_screen.set(Screen::Title);
 }
 
 fn all_assets_loaded(
-    player_assets: Option<Res<PlayerAssets>>,
+    duck_assets: Option<Res<DuckAssets>>,
     interaction_assets: Option<Res<InteractionAssets>>,
-    credits_music: Option<Res<CreditsMusic>>,
-    gameplay_music: Option<Res<GameplayMusic>>,
+    music_assets: Option<Res<MusicAssets>>,
+    quack_assets: Option<Res<QuackAssets>>,
+    cracker_assets: Option<Res<CrackerAssets>>,
+    bump_assets: Option<Res<BumpAssets>>,
+    level_assets: Option<Res<LevelAssets>>,
+    server_list_assets: Option<Res<ServerListAssets>>,
 ) -> bool {
-    player_assets.is_some()
+    duck_assets.is_some()
         && interaction_assets.is_some()
-        && credits_music.is_some()
-        && gameplay_music.is_some()
+        && music_assets.is_some()
+        && quack_assets.is_some()
+        && cracker_assets.is_some()
+        && bump_assets.is_some()
+        && level_assets.is_some()
+        && server_list_assets.is_some()
 }
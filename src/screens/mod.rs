@@ -2,7 +2,12 @@
 
 mod credits;
 mod gameplay;
+mod load_failed;
 mod loading;
+mod out_of_date;
+mod profile;
+mod server_select;
+mod settings;
 mod splash;
 mod title;
 
@@ -15,7 +20,12 @@ pub(super) fn plugin(app: &mut App) {
     app.add_plugins((
         credits::plugin,
         gameplay::plugin,
+        load_failed::plugin,
         loading::plugin,
+        out_of_date::plugin,
+        profile::plugin,
+        server_select::plugin,
+        settings::plugin,
         splash::plugin,
         title::plugin,
     ));
@@ -29,5 +39,10 @@ pub enum Screen {
     Loading,
     Title,
     Credits,
+    Settings,
+    Profile,
+    ServerSelect,
     Gameplay,
+    OutOfDate,
+    LoadFailed,
 }
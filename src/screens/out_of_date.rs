@@ -0,0 +1,52 @@
+//! Shown when the server can't speak our protocol version, which usually means
+//! a new version of the game has been deployed since this page was loaded.
+
+use bevy::prelude::*;
+
+use crate::{
+    demo::protocol::{ServerProtocol, PROTOCOL_VERSION},
+    screens::Screen,
+    theme::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::OutOfDate), spawn_out_of_date_screen);
+}
+
+fn spawn_out_of_date_screen(mut commands: Commands, server_protocol: Res<ServerProtocol>) {
+    let server_versions = server_protocol
+        .0
+        .as_ref()
+        .map(|hello| format!("{:?}", hello.protocol_versions))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::OutOfDate))
+        .with_children(|children| {
+            children.header("Please refresh");
+            children.label("This client is out of date for that pond.");
+            children.label(format!(
+                "Client protocol: {}, server protocols: {}",
+                PROTOCOL_VERSION, server_versions
+            ));
+
+            #[cfg(target_arch = "wasm32")]
+            children.button("Refresh").observe(reload_page);
+            children.button("Back").observe(enter_server_select_screen);
+        });
+}
+
+#[cfg(target_arch = "wasm32")]
+fn reload_page(_trigger: Trigger<OnPress>) {
+    if let Some(window) = web_sys::window() {
+        let _ = window.location().reload();
+    }
+}
+
+fn enter_server_select_screen(
+    _trigger: Trigger<OnPress>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    next_screen.set(Screen::ServerSelect);
+}
@@ -0,0 +1,85 @@
+//! A profile screen that can be accessed from the title screen, for picking
+//! the voice your duck quacks in.
+
+use bevy::prelude::*;
+
+use crate::{
+    audio::AudioCommands,
+    demo::quack_voice::{PlayerProfile, QuackAssets, QuackVoice, PROFILE_KEY},
+    persistence,
+    screens::Screen,
+    theme::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::Profile), spawn_profile_screen);
+    app.add_systems(OnExit(Screen::Profile), save_profile);
+    app.add_systems(Update, update_voice_label.run_if(in_state(Screen::Profile)));
+}
+
+#[derive(Component)]
+struct VoiceLabel;
+
+#[derive(Component)]
+struct VoiceButton(QuackVoice);
+
+fn spawn_profile_screen(mut commands: Commands, profile: Res<PlayerProfile>) {
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::Profile))
+        .with_children(|children| {
+            children.header("Profile");
+
+            children
+                .label(voice_label_text(&profile))
+                .insert(VoiceLabel);
+            for voice in QuackVoice::ALL {
+                children
+                    .button(voice.name())
+                    .insert(VoiceButton(voice))
+                    .observe(pick_voice);
+            }
+
+            children.button("Back").observe(enter_title_screen);
+        });
+}
+
+fn voice_label_text(profile: &PlayerProfile) -> String {
+    format!("Quack voice: {}", profile.quack_voice.name())
+}
+
+/// Pick the pressed voice and play it, so you know what you picked.
+fn pick_voice(
+    trigger: Trigger<OnPress>,
+    mut commands: Commands,
+    buttons: Query<&VoiceButton>,
+    quack_assets: Option<Res<QuackAssets>>,
+    mut profile: ResMut<PlayerProfile>,
+) {
+    let Ok(VoiceButton(voice)) = buttons.get(trigger.entity()) else {
+        return;
+    };
+    profile.quack_voice = *voice;
+
+    if let Some(quack_assets) = quack_assets {
+        commands.play_sound_effect(quack_assets.quack(*voice, 0.0));
+    }
+}
+
+fn update_voice_label(profile: Res<PlayerProfile>, mut labels: Query<&mut Text, With<VoiceLabel>>) {
+    if !profile.is_changed() {
+        return;
+    }
+
+    for mut text in &mut labels {
+        text.sections[0].value = voice_label_text(&profile);
+    }
+}
+
+fn save_profile(profile: Res<PlayerProfile>) {
+    persistence::save(PROFILE_KEY, &*profile);
+}
+
+fn enter_title_screen(_trigger: Trigger<OnPress>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Title);
+}
@@ -0,0 +1,194 @@
+//! A screen for picking which server to play on. We only connect once a
+//! server has been picked here.
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+
+use crate::{
+    demo::{
+        auth::LastConnectionError,
+        server_browser::{is_valid_server_url, RefreshServerBrowser, ServerBrowser},
+        websocket_connect::WebSocketConnectionEvents,
+    },
+    screens::Screen,
+    theme::{prelude::*, text_input::text_input_focused},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        OnEnter(Screen::ServerSelect),
+        (refresh_servers, spawn_server_select_screen).chain(),
+    );
+
+    app.add_systems(
+        Update,
+        (
+            respawn_server_rows,
+            update_server_status_text.run_if(resource_changed::<ServerBrowser>),
+        )
+            .chain()
+            .run_if(in_state(Screen::ServerSelect)),
+    );
+    app.add_systems(
+        Update,
+        return_to_title_screen.run_if(
+            in_state(Screen::ServerSelect)
+                .and_then(input_just_pressed(KeyCode::Escape))
+                // Escape unfocuses the custom server field instead.
+                .and_then(not(text_input_focused)),
+        ),
+    );
+}
+
+/// Holds one row per server in the [`ServerBrowser`].
+#[derive(Component)]
+struct ServerRows;
+
+/// The status label of the server at this index in the [`ServerBrowser`].
+#[derive(Component)]
+struct ServerStatusText(usize);
+
+#[derive(Component)]
+struct CustomServerInput;
+
+fn refresh_servers(mut refresh_events: EventWriter<RefreshServerBrowser>) {
+    refresh_events.send(RefreshServerBrowser);
+}
+
+fn spawn_server_select_screen(
+    mut commands: Commands,
+    mut last_connection_error: ResMut<LastConnectionError>,
+) {
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::ServerSelect))
+        .with_children(|children| {
+            children.header("Pick a pond");
+
+            // Only show why we got kicked back here once.
+            if let Some(error) = last_connection_error.0.take() {
+                children.label(error);
+            }
+
+            children.spawn((
+                Name::new("Server Rows"),
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(10.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                ServerRows,
+            ));
+
+            children.label("Or enter a server url:");
+            children
+                .text_input("wss://...")
+                .insert(CustomServerInput);
+            children.button("Join url").observe(join_custom_server);
+
+            children.button("Refresh").observe(refresh_server_list);
+            children.button("Back").observe(enter_title_screen);
+        });
+}
+
+/// The number of servers can change on refresh, so rebuild the rows whenever
+/// the list length no longer matches.
+fn respawn_server_rows(
+    mut commands: Commands,
+    browser: Res<ServerBrowser>,
+    rows: Query<(Entity, Option<&Children>), With<ServerRows>>,
+) {
+    for (rows_entity, children) in &rows {
+        let row_count = children.map_or(0, |children| children.len());
+        if row_count == browser.servers.len() {
+            continue;
+        }
+
+        commands.entity(rows_entity).despawn_descendants();
+        commands.entity(rows_entity).with_children(|children| {
+            for (index, server) in browser.servers.iter().enumerate() {
+                children
+                    .spawn((
+                        Name::new("Server Row"),
+                        NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            ..default()
+                        },
+                    ))
+                    .with_children(|row| {
+                        row.label(format!("{}: {}", server.name, server.status.describe()))
+                            .insert(ServerStatusText(index));
+
+                        let url = server.url.clone();
+                        row.button("Join").observe(
+                            move |_trigger: Trigger<OnPress>,
+                                  connect_events: EventWriter<WebSocketConnectionEvents>,
+                                  next_screen: ResMut<NextState<Screen>>| {
+                                join_server(url.clone(), connect_events, next_screen);
+                            },
+                        );
+                    });
+            }
+        });
+    }
+}
+
+fn update_server_status_text(
+    browser: Res<ServerBrowser>,
+    mut status_texts: Query<(&ServerStatusText, &mut Text)>,
+) {
+    for (status_text, mut text) in &mut status_texts {
+        if let Some(server) = browser.servers.get(status_text.0) {
+            text.sections[0].value = format!("{}: {}", server.name, server.status.describe());
+        }
+    }
+}
+
+fn join_server(
+    url: String,
+    mut connect_events: EventWriter<WebSocketConnectionEvents>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    info!("Joining server at {}", url);
+    connect_events.send(WebSocketConnectionEvents::SetupConnection { url });
+    next_screen.set(Screen::Gameplay);
+}
+
+fn join_custom_server(
+    _trigger: Trigger<OnPress>,
+    inputs: Query<&TextInput, With<CustomServerInput>>,
+    connect_events: EventWriter<WebSocketConnectionEvents>,
+    next_screen: ResMut<NextState<Screen>>,
+) {
+    let Ok(input) = inputs.get_single() else {
+        return;
+    };
+    let url = input.value.trim().to_string();
+    if !is_valid_server_url(&url) {
+        warn!("Not a valid server url: {:?}", url);
+        return;
+    }
+    join_server(url, connect_events, next_screen);
+}
+
+fn refresh_server_list(
+    _trigger: Trigger<OnPress>,
+    refresh_events: EventWriter<RefreshServerBrowser>,
+) {
+    refresh_servers(refresh_events);
+}
+
+fn enter_title_screen(_trigger: Trigger<OnPress>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Title);
+}
+
+fn return_to_title_screen(mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Title);
+}
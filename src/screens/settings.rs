@@ -0,0 +1,126 @@
+//! A settings screen that can be accessed from the title screen, with a
+//! volume slider and a mute button for each audio bus.
+
+use bevy::prelude::*;
+
+use crate::{
+    audio::{AudioBus, AudioSettings, AUDIO_SETTINGS_KEY},
+    persistence,
+    screens::Screen,
+    theme::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::Settings), spawn_settings_screen);
+    app.add_systems(OnExit(Screen::Settings), save_settings);
+    app.add_systems(
+        Update,
+        (update_volume_from_slider, update_volume_labels)
+            .chain()
+            .run_if(in_state(Screen::Settings)),
+    );
+}
+
+#[derive(Component)]
+struct VolumeSlider(AudioBus);
+
+#[derive(Component)]
+struct VolumeLabel(AudioBus);
+
+#[derive(Component)]
+struct MuteButton(AudioBus);
+
+fn spawn_settings_screen(mut commands: Commands, settings: Res<AudioSettings>) {
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::Settings))
+        .with_children(|children| {
+            children.header("Settings");
+
+            for bus in AudioBus::ALL {
+                children
+                    .label(volume_label_text(bus, &settings))
+                    .insert(VolumeLabel(bus));
+                children
+                    .slider(settings.bus(bus).volume)
+                    .insert(VolumeSlider(bus));
+                children
+                    .button(mute_button_text(bus, &settings))
+                    .insert(MuteButton(bus))
+                    .observe(toggle_mute);
+            }
+
+            children.button("Back").observe(enter_title_screen);
+        });
+}
+
+fn volume_label_text(bus: AudioBus, settings: &AudioSettings) -> String {
+    let bus_settings = settings.bus(bus);
+    let volume = (bus_settings.volume * 100.0).round();
+    if bus_settings.muted {
+        format!("{}: {}% (muted)", bus.name(), volume)
+    } else {
+        format!("{}: {}%", bus.name(), volume)
+    }
+}
+
+fn mute_button_text(bus: AudioBus, settings: &AudioSettings) -> &'static str {
+    if settings.bus(bus).muted {
+        "Unmute"
+    } else {
+        "Mute"
+    }
+}
+
+fn update_volume_from_slider(
+    sliders: Query<(&Slider, &VolumeSlider), Changed<Slider>>,
+    mut settings: ResMut<AudioSettings>,
+) {
+    for (slider, volume_slider) in &sliders {
+        let bus = volume_slider.0;
+        if settings.bus(bus).volume != slider.value {
+            settings.bus_mut(bus).volume = slider.value;
+        }
+    }
+}
+
+fn toggle_mute(
+    trigger: Trigger<OnPress>,
+    buttons: Query<&MuteButton>,
+    mut settings: ResMut<AudioSettings>,
+) {
+    let Ok(MuteButton(bus)) = buttons.get(trigger.entity()) else {
+        return;
+    };
+    let bus_settings = settings.bus_mut(*bus);
+    bus_settings.muted = !bus_settings.muted;
+}
+
+fn update_volume_labels(
+    settings: Res<AudioSettings>,
+    mut labels: Query<(&VolumeLabel, &mut Text)>,
+    mute_buttons: Query<(&MuteButton, &Children)>,
+    mut texts: Query<&mut Text, Without<VolumeLabel>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for (VolumeLabel(bus), mut text) in &mut labels {
+        text.sections[0].value = volume_label_text(*bus, &settings);
+    }
+    for (MuteButton(bus), children) in &mute_buttons {
+        let mut texts = texts.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value = mute_button_text(*bus, &settings).to_string();
+        }
+    }
+}
+
+fn save_settings(settings: Res<AudioSettings>) {
+    persistence::save(AUDIO_SETTINGS_KEY, &*settings);
+}
+
+fn enter_title_screen(_trigger: Trigger<OnPress>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Title);
+}
@@ -14,7 +14,9 @@ fn spawn_title_screen(mut commands: Commands) {
         .insert(StateScoped(Screen::Title))
         .with_children(|children| {
             
-            children.button("Play").observe(enter_gameplay_screen);
+            children.button("Play").observe(enter_server_select_screen);
+            children.button("Profile").observe(enter_profile_screen);
+            children.button("Settings").observe(enter_settings_screen);
             children.button("Credits").observe(enter_credits_screen);
 
             #[cfg(not(target_family = "wasm"))]
@@ -22,9 +24,19 @@ fn spawn_title_screen(mut commands: Commands) {
         });
 }
 
-fn enter_gameplay_screen(_trigger: Trigger<OnPress>, mut next_screen: ResMut<NextState<Screen>>) {
-    
-    next_screen.set(Screen::Gameplay);
+fn enter_server_select_screen(
+    _trigger: Trigger<OnPress>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    next_screen.set(Screen::ServerSelect);
+}
+
+fn enter_profile_screen(_trigger: Trigger<OnPress>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Profile);
+}
+
+fn enter_settings_screen(_trigger: Trigger<OnPress>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Settings);
 }
 
 fn enter_credits_screen(_trigger: Trigger<OnPress>, mut next_screen: ResMut<NextState<Screen>>) {
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::{asset_tracking::LoadResource, audio::SoundEffect};
+use crate::{asset_manifest::AssetManifest, asset_tracking::LoadResource, audio::AudioCommands};
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<InteractionPalette>();
@@ -67,17 +67,13 @@ pub struct InteractionAssets {
     press: Handle<AudioSource>,
 }
 
-impl InteractionAssets {
-    pub const PATH_BUTTON_HOVER: &'static str = "audio/sound_effects/button_hover.wav";
-    pub const PATH_BUTTON_PRESS: &'static str = "audio/sound_effects/button_press.wav";
-}
-
 impl FromWorld for InteractionAssets {
     fn from_world(world: &mut World) -> Self {
+        let manifest = world.resource::<AssetManifest>();
         let assets = world.resource::<AssetServer>();
         Self {
-            hover: assets.load(Self::PATH_BUTTON_HOVER),
-            press: assets.load(Self::PATH_BUTTON_PRESS),
+            hover: manifest.load_sound(assets, "button_hover"),
+            press: manifest.load_sound(assets, "button_press"),
         }
     }
 }
@@ -93,12 +89,9 @@ fn trigger_interaction_sound_effect(
             Interaction::Pressed => interaction_assets.press.clone(),
             _ => continue,
         };
-        commands.spawn((
-            AudioBundle {
-                source,
-                settings: PlaybackSettings::DESPAWN,
-            },
-            SoundEffect,
-        ));
+        commands.play_sound_effect(AudioBundle {
+            source,
+            settings: PlaybackSettings::DESPAWN,
+        });
     }
 }
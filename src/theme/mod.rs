@@ -5,6 +5,8 @@
 
 pub mod interaction;
 pub mod palette;
+pub mod slider;
+pub mod text_input;
 pub mod widgets;
 
 #[allow(unused_imports)]
@@ -12,6 +14,8 @@ pub mod prelude {
     pub use super::{
         interaction::{InteractionPalette, OnPress},
         palette as ui_palette,
+        slider::Slider,
+        text_input::TextInput,
         widgets::{Containers as _, Widgets as _},
     };
 }
@@ -19,5 +23,5 @@ pub mod prelude {
 use bevy::prelude::*;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins(interaction::plugin);
+    app.add_plugins((interaction::plugin, slider::plugin, text_input::plugin));
 }
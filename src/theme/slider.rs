@@ -0,0 +1,45 @@
+//! A minimal horizontal slider. Click or drag along it to set its value.
+
+use bevy::{prelude::*, ui::RelativeCursorPosition};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Update, (drag_slider, update_slider_fill).chain());
+}
+
+/// The current value of a slider spawned with [`Widgets::slider`](super::widgets::Widgets::slider),
+/// from `0.0` at the left end to `1.0` at the right end.
+#[derive(Component, Debug, Default)]
+pub struct Slider {
+    pub value: f32,
+}
+
+/// Marks the node that fills a [`Slider`] up to its value.
+#[derive(Component)]
+pub struct SliderFill;
+
+fn drag_slider(mut sliders: Query<(&Interaction, &RelativeCursorPosition, &mut Slider)>) {
+    for (interaction, cursor, mut slider) in &mut sliders {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+        let Some(cursor) = cursor.normalized else {
+            continue;
+        };
+        let value = cursor.x.clamp(0.0, 1.0);
+        if slider.value != value {
+            slider.value = value;
+        }
+    }
+}
+
+fn update_slider_fill(
+    sliders: Query<(&Slider, &Children), Changed<Slider>>,
+    mut fills: Query<&mut Style, With<SliderFill>>,
+) {
+    for (slider, children) in &sliders {
+        let mut fills = fills.iter_many_mut(children);
+        while let Some(mut style) = fills.fetch_next() {
+            style.width = Val::Percent(slider.value.clamp(0.0, 1.0) * 100.0);
+        }
+    }
+}
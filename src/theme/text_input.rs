@@ -0,0 +1,112 @@
+//! A minimal single-line text input. Click it to focus, then type.
+
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+};
+
+use crate::theme::palette::{BUTTON_TEXT, LABEL_TEXT};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (focus_text_input, type_into_text_input, update_text_input_text).chain(),
+    );
+}
+
+/// The current value of a text input spawned with [`Widgets::text_input`](super::widgets::Widgets::text_input).
+#[derive(Component, Debug, Default)]
+pub struct TextInput {
+    pub value: String,
+    pub placeholder: String,
+    pub focused: bool,
+}
+
+/// Whether one of the text inputs is being typed into, e.g. to keep keyboard
+/// shortcuts out of its way.
+pub fn text_input_focused(inputs: Query<&TextInput>) -> bool {
+    inputs.iter().any(|input| input.focused)
+}
+
+/// Marks the text entity that displays a [`TextInput`]'s value.
+#[derive(Component)]
+pub struct TextInputText;
+
+fn focus_text_input(
+    interaction_query: Query<(Entity, &Interaction), (Changed<Interaction>, With<TextInput>)>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut inputs: Query<(Entity, &mut TextInput)>,
+) {
+    let pressed_input = interaction_query
+        .iter()
+        .find(|(_, interaction)| matches!(interaction, Interaction::Pressed))
+        .map(|(entity, _)| entity);
+
+    // Clicking anywhere else drops focus.
+    if pressed_input.is_none() && !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    for (entity, mut input) in &mut inputs {
+        let focused = Some(entity) == pressed_input;
+        if input.focused != focused {
+            input.focused = focused;
+        }
+    }
+}
+
+fn type_into_text_input(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut inputs: Query<&mut TextInput>,
+) {
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        for mut input in inputs.iter_mut().filter(|input| input.focused) {
+            match &event.logical_key {
+                Key::Character(characters) => {
+                    input
+                        .value
+                        .extend(characters.chars().filter(|c| !c.is_control()));
+                }
+                Key::Space => input.value.push(' '),
+                Key::Backspace => {
+                    input.value.pop();
+                }
+                Key::Escape | Key::Enter => {
+                    input.focused = false;
+                    // Don't let the screen treat it as a way out too.
+                    keys.clear_just_pressed(KeyCode::Escape);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn update_text_input_text(
+    inputs: Query<(&TextInput, &Children), Changed<TextInput>>,
+    mut texts: Query<&mut Text, With<TextInputText>>,
+) {
+    for (input, children) in &inputs {
+        let mut texts = texts.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            let section = &mut text.sections[0];
+            if input.value.is_empty() && !input.focused {
+                section.value = input.placeholder.clone();
+                section.style.color = LABEL_TEXT;
+            } else {
+                section.value = input.value.clone();
+                if input.focused {
+                    section.value.push('|');
+                }
+                section.style.color = BUTTON_TEXT;
+            }
+        }
+    }
+}
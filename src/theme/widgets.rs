@@ -1,14 +1,13 @@
 //! Helper traits for creating common widgets.
 
-use bevy::{ecs::system::EntityCommands, prelude::*, ui::Val::*};
+use bevy::{ecs::system::EntityCommands, prelude::*, ui::RelativeCursorPosition, ui::Val::*};
 
-use crate::theme::{interaction::InteractionPalette, palette::*};
-
-// Component to hold the current text input
-#[derive(Component)]
-struct TextInput {
-    value: String,
-}
+use crate::theme::{
+    interaction::InteractionPalette,
+    palette::*,
+    slider::{Slider, SliderFill},
+    text_input::{TextInput, TextInputText},
+};
 
 /// An extension trait for spawning UI widgets
 pub trait Widgets {
@@ -23,8 +22,11 @@ pub trait Widgets {
     /// Spawn a simple text label.
     fn label(&mut self, text: impl Into<String>) -> EntityCommands;
 
-    // Spawn a text input.
-    // fn text_input(&mut self, text: impl Into<String>) -> EntityCommands;
+    /// Spawn a single-line text input that shows `placeholder` while empty.
+    fn text_input(&mut self, placeholder: impl Into<String>) -> EntityCommands;
+
+    /// Spawn a horizontal slider starting at `value`, from `0.0` to `1.0`.
+    fn slider(&mut self, value: f32) -> EntityCommands;
 }
 
 impl<T: Spawn> Widgets for T {
@@ -115,6 +117,79 @@ impl<T: Spawn> Widgets for T {
         entity
     }
 
+    fn text_input(&mut self, placeholder: impl Into<String>) -> EntityCommands {
+        let placeholder = placeholder.into();
+        let mut entity = self.spawn((
+            Name::new("Text Input"),
+            ButtonBundle {
+                style: Style {
+                    width: Px(500.0),
+                    height: Px(50.0),
+                    padding: UiRect::horizontal(Px(10.0)),
+                    justify_content: JustifyContent::Start,
+                    align_items: AlignItems::Center,
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                background_color: BackgroundColor(NODE_BACKGROUND),
+                ..default()
+            },
+            TextInput {
+                placeholder: placeholder.clone(),
+                ..default()
+            },
+        ));
+        entity.with_children(|children| {
+            children.spawn((
+                Name::new("Text Input Text"),
+                TextBundle::from_section(
+                    placeholder,
+                    TextStyle {
+                        font_size: 24.0,
+                        color: LABEL_TEXT,
+                        ..default()
+                    },
+                ),
+                TextInputText,
+            ));
+        });
+        entity
+    }
+
+    fn slider(&mut self, value: f32) -> EntityCommands {
+        let value = value.clamp(0.0, 1.0);
+        let mut entity = self.spawn((
+            Name::new("Slider"),
+            ButtonBundle {
+                style: Style {
+                    width: Px(500.0),
+                    height: Px(30.0),
+                    ..default()
+                },
+                background_color: BackgroundColor(NODE_BACKGROUND),
+                ..default()
+            },
+            RelativeCursorPosition::default(),
+            Slider { value },
+        ));
+        entity.with_children(|children| {
+            children.spawn((
+                Name::new("Slider Fill"),
+                NodeBundle {
+                    style: Style {
+                        width: Percent(value * 100.0),
+                        height: Percent(100.0),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(LABEL_TEXT),
+                    ..default()
+                },
+                SliderFill,
+            ));
+        });
+        entity
+    }
+
     // fn quack_button(&mut self, text: impl Into<String>) -> EntityCommands {
     //     let mut entity = self.spawn((
     //         Name::new("QuackButton"),
@@ -0,0 +1,390 @@
+//! Packs the `assets` folder for a release build.
+//!
+//! Reads `assets/assets.manifest.json` and writes a copy of the folder that:
+//! - packs the images the manifest puts in an `atlas` into one image per atlas,
+//! - keeps one audio format per named sound, the best one for the target,
+//! - leaves out the files the manifest `exclude`s,
+//! - has a manifest that points the game at the results.
+//!
+//! Run it from the repository root:
+//!
+//! ```sh
+//! cargo run -p pack_assets -- --target web --out target/packed-assets/web
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use image::{GenericImage, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+const MANIFEST_FILE: &str = "assets.manifest.json";
+/// Space left between images in an atlas, so filtering doesn't bleed.
+const ATLAS_PADDING: u32 = 2;
+/// Atlases wider than this wrap onto a new shelf.
+const MAX_ATLAS_WIDTH: u32 = 2048;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Web,
+    Native,
+}
+
+impl Target {
+    fn parse(name: &str) -> Result<Target> {
+        match name {
+            "web" => Ok(Target::Web),
+            "native" => Ok(Target::Native),
+            _ => Err(format!("unknown target {:?}, expected web or native", name).into()),
+        }
+    }
+
+    /// Audio formats, most preferred first. The web wants small downloads;
+    /// native builds would rather not spend time decoding.
+    fn audio_formats(self) -> &'static [&'static str] {
+        match self {
+            Target::Web => &["ogg", "mp3", "wav"],
+            Target::Native => &["wav", "ogg", "mp3"],
+        }
+    }
+}
+
+struct Args {
+    target: Target,
+    assets: PathBuf,
+    out: PathBuf,
+}
+
+impl Args {
+    fn parse() -> Result<Args> {
+        let mut target = Target::Web;
+        let mut assets = PathBuf::from("assets");
+        let mut out = None;
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--target" => target = Target::parse(&value()?)?,
+                "--assets" => assets = PathBuf::from(value()?),
+                "--out" => out = Some(PathBuf::from(value()?)),
+                _ => {
+                    return Err(format!(
+                        "unknown argument {:?}\n\
+                         usage: pack_assets [--target web|native] [--assets DIR] [--out DIR]",
+                        arg
+                    )
+                    .into())
+                }
+            }
+        }
+
+        let out = out.unwrap_or_else(|| {
+            let target = match target {
+                Target::Web => "web",
+                Target::Native => "native",
+            };
+            PathBuf::from("target/packed-assets").join(target)
+        });
+        Ok(Args {
+            target,
+            assets,
+            out,
+        })
+    }
+}
+
+/// `assets/assets.manifest.json`, as written by hand.
+#[derive(Deserialize)]
+struct SourceManifest {
+    #[serde(default)]
+    images: BTreeMap<String, SourceImage>,
+    #[serde(default)]
+    sounds: BTreeMap<String, String>,
+    #[serde(default)]
+    fonts: BTreeMap<String, String>,
+    /// Files (relative to the `assets` folder) the game doesn't use.
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct SourceImage {
+    path: String,
+    #[serde(default)]
+    sampler: Sampler,
+    /// The atlas to pack the image into, if any.
+    #[serde(default)]
+    atlas: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+enum Sampler {
+    #[default]
+    Linear,
+    Nearest,
+}
+
+/// The manifest the game reads from the packed folder. See
+/// `src/asset_manifest.rs`.
+#[derive(Serialize, Default)]
+struct PackedManifest {
+    images: BTreeMap<String, PackedImage>,
+    sounds: BTreeMap<String, String>,
+    fonts: BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+struct PackedImage {
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rect: Option<Rect>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    sampler: Sampler,
+}
+
+/// Serialized the way Bevy's `URect` is.
+#[derive(Serialize, Clone, Copy)]
+struct Rect {
+    min: [u32; 2],
+    max: [u32; 2],
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("pack_assets: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let args = Args::parse()?;
+    let source: SourceManifest = serde_json::from_slice(
+        &fs::read(args.assets.join(MANIFEST_FILE))
+            .map_err(|error| format!("reading {}: {}", MANIFEST_FILE, error))?,
+    )?;
+
+    if args.out.exists() {
+        fs::remove_dir_all(&args.out)?;
+    }
+    fs::create_dir_all(&args.out)?;
+
+    let mut packed = PackedManifest::default();
+    let mut skip: HashSet<String> = source.exclude.iter().cloned().collect();
+    skip.insert(MANIFEST_FILE.to_string());
+
+    // Images: pack the ones in an atlas, pass the rest through.
+    let mut atlases: BTreeMap<&str, Vec<(&String, &SourceImage)>> = BTreeMap::new();
+    for (name, image) in &source.images {
+        match &image.atlas {
+            Some(atlas) => {
+                atlases.entry(atlas).or_default().push((name, image));
+                skip.insert(image.path.clone());
+            }
+            None => {
+                packed.images.insert(
+                    name.clone(),
+                    PackedImage {
+                        path: image.path.clone(),
+                        rect: None,
+                        source: None,
+                        sampler: image.sampler,
+                    },
+                );
+            }
+        }
+    }
+    for (atlas, images) in atlases {
+        pack_atlas(&args, atlas, &images, &mut packed)?;
+    }
+
+    // Sounds: of all the formats a named sound comes in, keep the best one.
+    let files = list_files(&args.assets)?;
+    for (name, path) in &source.sounds {
+        let formats = sibling_formats(&files, path);
+        let chosen = args
+            .target
+            .audio_formats()
+            .iter()
+            .find_map(|format| formats.get(*format))
+            .cloned()
+            .unwrap_or_else(|| path.clone());
+        for other in formats.values().filter(|other| **other != chosen) {
+            skip.insert(other.clone());
+        }
+        packed.sounds.insert(name.clone(), chosen);
+    }
+    // Two names could share a file in different formats; never drop a chosen one.
+    for chosen in packed.sounds.values() {
+        skip.remove(chosen);
+    }
+
+    // Fonts are copied as they are, along with everything else.
+    packed.fonts = source.fonts.clone();
+
+    // Everything else is copied as it is.
+    let mut copied_bytes = 0;
+    for file in &files {
+        let file_name = Path::new(file).file_name().and_then(|name| name.to_str());
+        if skip.contains(file) || file_name.is_some_and(|name| name.starts_with('.')) {
+            continue;
+        }
+        let to = args.out.join(file);
+        fs::create_dir_all(to.parent().unwrap())?;
+        copied_bytes += fs::copy(args.assets.join(file), to)?;
+    }
+
+    fs::write(
+        args.out.join(MANIFEST_FILE),
+        serde_json::to_string_pretty(&packed)?,
+    )?;
+
+    let source_bytes: u64 = files
+        .iter()
+        .map(|file| fs::metadata(args.assets.join(file)).map_or(0, |metadata| metadata.len()))
+        .sum();
+    let packed_bytes = dir_size(&args.out)?;
+    println!(
+        "Packed {} for {:?}: {} KiB -> {} KiB ({} KiB copied as is)",
+        args.assets.display(),
+        args.target,
+        source_bytes / 1024,
+        packed_bytes / 1024,
+        copied_bytes / 1024,
+    );
+    println!("Wrote {}", args.out.display());
+    Ok(())
+}
+
+/// Pack `images` into `atlases/<atlas>.png`, on shelves from tallest to
+/// shortest.
+fn pack_atlas(
+    args: &Args,
+    atlas: &str,
+    images: &[(&String, &SourceImage)],
+    packed: &mut PackedManifest,
+) -> Result<()> {
+    let sampler = images[0].1.sampler;
+    if let Some((name, _)) = images.iter().find(|(_, image)| image.sampler != sampler) {
+        return Err(format!(
+            "{:?} doesn't use the same sampler as the rest of atlas {:?}",
+            name, atlas
+        )
+        .into());
+    }
+
+    let mut loaded = Vec::new();
+    for &(name, image) in images {
+        let pixels = image::open(args.assets.join(&image.path))
+            .map_err(|error| format!("reading {}: {}", image.path, error))?
+            .to_rgba8();
+        loaded.push((name, image, pixels));
+    }
+    loaded.sort_by_key(|(name, _, pixels)| (std::cmp::Reverse(pixels.height()), *name));
+
+    // Place the images left to right, starting a new shelf when one's full.
+    let mut rects = HashMap::new();
+    let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+    let (mut width, mut height) = (0, 0);
+    for (name, _, pixels) in &loaded {
+        if x > 0 && x + pixels.width() > MAX_ATLAS_WIDTH {
+            x = 0;
+            y += shelf_height + ATLAS_PADDING;
+            shelf_height = 0;
+        }
+        let rect = Rect {
+            min: [x, y],
+            max: [x + pixels.width(), y + pixels.height()],
+        };
+        rects.insert(*name, rect);
+        x += pixels.width() + ATLAS_PADDING;
+        shelf_height = shelf_height.max(pixels.height());
+        width = width.max(rect.max[0]);
+        height = height.max(rect.max[1]);
+    }
+
+    let mut atlas_image = RgbaImage::new(width, height);
+    for (name, _, pixels) in &loaded {
+        let rect = rects[name];
+        atlas_image.copy_from(pixels, rect.min[0], rect.min[1])?;
+    }
+    let path = format!("atlases/{}.png", atlas);
+    let to = args.out.join(&path);
+    fs::create_dir_all(to.parent().unwrap())?;
+    atlas_image.save(&to)?;
+
+    for (name, image, _) in &loaded {
+        packed.images.insert(
+            (*name).clone(),
+            PackedImage {
+                path: path.clone(),
+                rect: Some(rects[name]),
+                source: Some(image.path.clone()),
+                sampler,
+            },
+        );
+    }
+    println!(
+        "Packed {} images into {} ({}x{})",
+        loaded.len(),
+        path,
+        width,
+        height
+    );
+    Ok(())
+}
+
+/// Every file under `dir`, relative to it and with `/` separators.
+fn list_files(dir: &Path) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(relative) = dirs.pop() {
+        for entry in fs::read_dir(dir.join(&relative))? {
+            let entry = entry?;
+            let path = relative.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                dirs.push(path);
+            } else {
+                let parts: Vec<_> = path.iter().map(|part| part.to_string_lossy()).collect();
+                files.push(parts.join("/"));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// The formats `path` comes in, by extension, e.g. `step1.wav` and
+/// `step1.ogg` for `audio/sound_effects/step1.wav`.
+fn sibling_formats(files: &[String], path: &str) -> BTreeMap<String, String> {
+    let stem = |file: &str| {
+        file.rsplit_once('.')
+            .map_or(file, |(stem, _)| stem)
+            .to_string()
+    };
+    let path_stem = stem(path);
+    files
+        .iter()
+        .filter(|file| stem(file) == path_stem)
+        .filter_map(|file| {
+            let (_, extension) = file.rsplit_once('.')?;
+            Some((extension.to_ascii_lowercase(), file.clone()))
+        })
+        .collect()
+}
+
+fn dir_size(dir: &Path) -> Result<u64> {
+    let mut size = 0;
+    for file in list_files(dir)? {
+        size += fs::metadata(dir.join(file))?.len();
+    }
+    Ok(size)
+}
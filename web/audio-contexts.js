@@ -0,0 +1,23 @@
+// Lets the game ask about and resume the page's `AudioContext`s, which
+// `restart-audio-context.js` collects in `self.quackersAudioContexts`.
+// See `src/demo/check_silent_mode.rs`.
+
+// Closed contexts can't be resumed, so only suspended ones keep us quiet.
+function suspendedContexts() {
+    return (self.quackersAudioContexts || []).filter(
+        (context) => context.state === 'suspended',
+    );
+}
+
+// `undefined` until the game has created an audio context.
+export function audioContextsBlocked() {
+    const contexts = self.quackersAudioContexts;
+    if (!contexts || contexts.length === 0) {
+        return undefined;
+    }
+    return suspendedContexts().length > 0;
+}
+
+export function resumeAudioContexts() {
+    suspendedContexts().forEach((context) => context.resume());
+}
@@ -2,6 +2,9 @@
 (function () {
     // An array of all contexts to resume on the page
     const audioContextList = [];
+    // Shared with the game, which shows a "Tap to enable sound" prompt while
+    // any of them is suspended (see `src/demo/check_silent_mode.rs`).
+    self.quackersAudioContexts = audioContextList;
 
     // An array of various user interaction events we should listen for
     const userInputEventNames = [
@@ -27,26 +30,14 @@
         },
     });
 
-    // To resume all AudioContexts being tracked
+    // To resume all AudioContexts being tracked. We keep listening after
+    // they're running, since the browser can suspend them again later.
     function resumeAllContexts(event) {
-        let count = 0;
-
         audioContextList.forEach(context => {
-            if (context.state !== 'running') {
+            if (context.state === 'suspended') {
                 context.resume();
-            } else {
-                count++;
             }
         });
-
-        // If all the AudioContexts have now resumed then we
-        // unbind all the event listeners from the page to prevent
-        // unnecessary resume attempts
-        if (count == audioContextList.length) {
-            userInputEventNames.forEach(eventName => {
-                document.removeEventListener(eventName, resumeAllContexts);
-            });
-        }
     }
 
     // We bind the resume function for each user interaction